@@ -0,0 +1,40 @@
+use serenity::all::{
+    CommandInteraction, Context, CreateAutocompleteResponse, CreateInteractionResponse,
+};
+
+use crate::state::BotState;
+
+pub async fn run(bot: &BotState, ctx: &Context, interaction: &CommandInteraction) {
+    let Some(option) = interaction.data.autocomplete() else {
+        return;
+    };
+
+    /* Every command with a "character" option only accepts characters owned by the user. */
+    if option.name != "character" {
+        return;
+    }
+
+    let value = option.value.to_lowercase();
+
+    let mut response = CreateAutocompleteResponse::new();
+    /* Discord allows at most 25 choices. */
+    for entry in bot
+        .index_find(interaction.user.id.get())
+        .await
+        .into_iter()
+        .filter(|e| e.eve_character_name.to_lowercase().contains(&value))
+        .take(25)
+    {
+        let name = if entry.expired {
+            format!("{} (expired)", entry.eve_character_name)
+        } else {
+            entry.eve_character_name
+        };
+
+        response = response.add_string_choice(name, entry.eve_character_id.to_string());
+    }
+
+    let _ = interaction
+        .create_response(&ctx.http, CreateInteractionResponse::Autocomplete(response))
+        .await;
+}
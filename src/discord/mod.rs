@@ -2,10 +2,13 @@ use serenity::all::{ActivityData, Command, GuildId, Interaction, Ready};
 use serenity::{async_trait, prelude::*};
 use tracing::{error, info};
 
-use crate::monitor::Monitor;
+use crate::monitor::{Monitor, MonitorConfig};
 use crate::state::BotState;
 
+mod autocomplete;
 mod monitor;
+mod status;
+mod unmonitor;
 
 #[async_trait]
 impl EventHandler for BotState {
@@ -22,19 +25,33 @@ impl EventHandler for BotState {
         ctx.set_activity(Some(ActivityData::custom("Monitoring your skills")));
 
         /* Register our global commands; we only use ephemeral responses, so we don't care what channel it is done in. */
-        if let Err(why) = Command::create_global_command(&ctx.http, monitor::register()).await {
-            error!("Error creating global command: {why:?}");
+        for command in [
+            monitor::register(),
+            status::register(),
+            unmonitor::register(),
+        ] {
+            if let Err(why) = Command::create_global_command(&ctx.http, command).await {
+                error!("Error creating global command: {why:?}");
+            }
         }
 
-        Monitor::start(self.clone()).await;
+        Monitor::start(self.clone(), MonitorConfig::from_env()).await;
     }
 
     async fn interaction_create(&self, ctx: Context, interaction: Interaction) {
-        if let Interaction::Command(command) = interaction {
-            match command.data.name.as_str() {
-                "monitor" => monitor::run(&self, &ctx, &command).await,
-                _ => {}
-            };
+        match interaction {
+            Interaction::Command(command) => {
+                match command.data.name.as_str() {
+                    "monitor" => monitor::run(self, &ctx, &command).await,
+                    "status" => status::run(self, &ctx, &command).await,
+                    "unmonitor" => unmonitor::run(self, &ctx, &command).await,
+                    _ => {}
+                };
+            }
+            Interaction::Autocomplete(interaction) => {
+                autocomplete::run(self, &ctx, &interaction).await;
+            }
+            _ => {}
         }
     }
 }
@@ -10,7 +10,13 @@ use crate::state::BotState;
 
 pub async fn run(bot: &BotState, ctx: &Context, command: &CommandInteraction) {
     let state = CsrfToken::new_random();
-    bot.pending_create(state.secret().clone(), command).await;
+    bot.pending_create(
+        state.secret().clone(),
+        command.guild_id.unwrap().get(),
+        command.user.id.get(),
+        command.token.clone(),
+    )
+    .await;
 
     let webserver_url =
         env::var("WEBSERVER_URL").expect("Expected WEBSERVER_URL in the environment");
@@ -0,0 +1,111 @@
+use serenity::all::{
+    CommandInteraction, Context, CreateCommand, CreateEmbed, CreateInteractionResponse,
+    CreateInteractionResponseMessage,
+};
+
+use crate::monitor::{format_sp, level_to_roman, StorageV2};
+use crate::state::BotState;
+
+/* Discord allows at most 25 fields in an embed. */
+const MAX_FIELDS: usize = 25;
+
+async fn character_to_field(bot: &BotState, storage: &StorageV2) -> String {
+    let now = chrono::Utc::now();
+    let mut value = String::new();
+
+    if storage.expired {
+        value += "**Monitoring suspended.** Use /monitor to re-authenticate.\n";
+    }
+
+    /* Skills that finished since the last refresh are still in the stored queue; skip those. */
+    let training = storage
+        .skill_queue
+        .iter()
+        .find(|q| q.finish_date.is_none() || q.finish_date > Some(now));
+
+    match training {
+        Some(queue) => {
+            let skill_name = bot
+                .cached_skill_name(queue.skill_id)
+                .await
+                .unwrap_or(format!("Skill {}", queue.skill_id));
+
+            match queue.finish_date {
+                Some(finish_date) => {
+                    value += &format!(
+                        "Training `{} {}`, finishes <t:{}:R>.\n",
+                        skill_name,
+                        level_to_roman(queue.finished_level),
+                        finish_date.timestamp()
+                    );
+                }
+                None => {
+                    value += &format!(
+                        "Training `{} {}` is paused.\n",
+                        skill_name,
+                        level_to_roman(queue.finished_level)
+                    );
+                }
+            }
+
+            match storage.skill_queue.last().and_then(|q| q.finish_date) {
+                Some(finish_date) => {
+                    value += &format!("Skill queue finishes <t:{}:R>.\n", finish_date.timestamp());
+                }
+                None => {
+                    value += "Skill queue will never finish.\n";
+                }
+            }
+        }
+        None => {
+            value += "Skill queue is empty.\n";
+        }
+    }
+
+    value += &format!("Total SP: {}\n", format_sp(storage.total_sp));
+    value += &format!(
+        "Unallocated SP: {}\n",
+        format_sp(storage.unallocated_sp as i64)
+    );
+
+    value
+}
+
+pub async fn run(bot: &BotState, ctx: &Context, command: &CommandInteraction) {
+    let characters = bot.find_eve_characters(command.user.id.get()).await;
+
+    let message = if characters.is_empty() {
+        CreateInteractionResponseMessage::new()
+            .content("You are not monitoring any characters. Use /monitor to start.")
+    } else {
+        let mut embed = CreateEmbed::new().title("Your characters");
+
+        for storage in characters.iter().take(MAX_FIELDS) {
+            embed = embed.field(
+                &storage.eve_character_name,
+                character_to_field(bot, storage).await,
+                false,
+            );
+        }
+        if characters.len() > MAX_FIELDS {
+            embed = embed.description(format!(
+                "Only showing {} out of {} characters.",
+                MAX_FIELDS,
+                characters.len()
+            ));
+        }
+
+        CreateInteractionResponseMessage::new().embed(embed)
+    };
+
+    let _ = command
+        .create_response(
+            &ctx.http,
+            CreateInteractionResponse::Message(message.ephemeral(true)),
+        )
+        .await;
+}
+
+pub fn register() -> CreateCommand {
+    CreateCommand::new("status").description("Show the skill training status of your characters.")
+}
@@ -0,0 +1,103 @@
+use serenity::all::{
+    CommandInteraction, CommandOptionType, Context, CreateCommand, CreateCommandOption,
+    CreateInteractionResponse, CreateInteractionResponseMessage, EditInteractionResponse,
+    ResolvedValue,
+};
+
+use crate::monitor::ChannelAction;
+use crate::state::BotState;
+
+pub async fn run(bot: &BotState, ctx: &Context, command: &CommandInteraction) {
+    let mut character = "";
+    let mut delete = false;
+    let mut channel_action = ChannelAction::Keep;
+
+    for option in command.data.options() {
+        match (option.name, option.value) {
+            ("character", ResolvedValue::String(value)) => character = value,
+            ("delete", ResolvedValue::Boolean(value)) => delete = value,
+            ("channel", ResolvedValue::String("archive")) => {
+                channel_action = ChannelAction::Archive
+            }
+            ("channel", ResolvedValue::String("delete")) => channel_action = ChannelAction::Delete,
+            _ => {}
+        }
+    }
+
+    /* Revoking the token and cleaning up the channel can take a while; defer the response. */
+    let _ = command
+        .create_response(
+            &ctx.http,
+            CreateInteractionResponse::Defer(
+                CreateInteractionResponseMessage::new().ephemeral(true),
+            ),
+        )
+        .await;
+
+    let discord_character_id = command.user.id.get();
+
+    /* Characters can be selected by either name or ID. */
+    let storage = bot
+        .find_eve_characters(discord_character_id)
+        .await
+        .into_iter()
+        .find(|s| {
+            s.eve_character_name.eq_ignore_ascii_case(character)
+                || s.eve_character_id.to_string() == character
+        });
+
+    let message = match storage {
+        Some(storage) => {
+            let result = bot
+                .uninstall_monitor(
+                    storage.eve_character_id,
+                    discord_character_id,
+                    delete,
+                    channel_action,
+                )
+                .await;
+
+            match result {
+                Ok(()) => format!("Stopped monitoring {}.", storage.eve_character_name),
+                Err(error) => format!(
+                    "Failed to stop monitoring {}: {}",
+                    storage.eve_character_name, error
+                ),
+            }
+        }
+        None => format!("You are not monitoring a character named {}.", character),
+    };
+
+    let _ = command
+        .edit_response(&ctx.http, EditInteractionResponse::new().content(message))
+        .await;
+}
+
+pub fn register() -> CreateCommand {
+    CreateCommand::new("unmonitor")
+        .description("Stop monitoring skills for an EVE character.")
+        .add_option(
+            CreateCommandOption::new(
+                CommandOptionType::String,
+                "character",
+                "Name of the EVE character to stop monitoring.",
+            )
+            .required(true)
+            .set_autocomplete(true),
+        )
+        .add_option(CreateCommandOption::new(
+            CommandOptionType::Boolean,
+            "delete",
+            "Delete all stored data of this character, instead of allowing to resume with /monitor.",
+        ))
+        .add_option(
+            CreateCommandOption::new(
+                CommandOptionType::String,
+                "channel",
+                "What to do with the channel of this character.",
+            )
+            .add_string_choice("Keep", "keep")
+            .add_string_choice("Archive", "archive")
+            .add_string_choice("Delete", "delete"),
+        )
+}
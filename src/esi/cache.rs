@@ -0,0 +1,100 @@
+use std::collections::HashMap;
+
+use chrono::Utc;
+use serde::de::DeserializeOwned;
+
+use super::{Esi, EsiResponse};
+
+struct CacheEntry {
+    etag: String,
+    body: String,
+}
+
+/// Last response per character and endpoint, so ESI can answer with "304 Not Modified".
+#[derive(Default)]
+pub struct EsiCache(HashMap<(u64, &'static str), CacheEntry>);
+
+fn parse_expires(response: &reqwest::Response) -> Option<chrono::DateTime<Utc>> {
+    let expires = response.headers().get("Expires")?.to_str().ok()?;
+
+    chrono::DateTime::parse_from_rfc2822(expires)
+        .ok()
+        .map(|expires| expires.with_timezone(&Utc))
+}
+
+impl Esi {
+    pub(super) async fn get_cached<T: DeserializeOwned>(
+        &self,
+        access_token: &str,
+        character_id: u64,
+        endpoint: &'static str,
+        url: &str,
+    ) -> Result<EsiResponse<T>, String> {
+        let etag = {
+            let cache = self.cache.lock().await;
+            cache
+                .0
+                .get(&(character_id, endpoint))
+                .map(|entry| entry.etag.clone())
+        };
+
+        let mut request = self
+            .http
+            .get(url)
+            .header("Authorization", format!("Bearer {}", access_token));
+        if let Some(etag) = etag {
+            request = request.header("If-None-Match", etag);
+        }
+
+        let response = self.send(request).await?;
+
+        let status = response.status().as_u16();
+        let expires = parse_expires(&response);
+        let etag = response
+            .headers()
+            .get("ETag")
+            .and_then(|etag| etag.to_str().ok())
+            .map(|etag| etag.to_string());
+        let body = response.text().await.map_err(|e| e.to_string())?;
+
+        let mut cache = self.cache.lock().await;
+        match status {
+            200 => {
+                let data = serde_json::from_str(&body).map_err(|e| e.to_string())?;
+
+                match etag {
+                    Some(etag) => {
+                        cache
+                            .0
+                            .insert((character_id, endpoint), CacheEntry { etag, body });
+                    }
+                    None => {
+                        cache.0.remove(&(character_id, endpoint));
+                    }
+                }
+
+                Ok(EsiResponse {
+                    data,
+                    expires,
+                    modified: true,
+                })
+            }
+            304 => {
+                let entry = cache
+                    .0
+                    .get(&(character_id, endpoint))
+                    .ok_or("Not modified, but nothing in cache".to_string())?;
+
+                Ok(EsiResponse {
+                    data: serde_json::from_str(&entry.body).map_err(|e| e.to_string())?,
+                    expires,
+                    modified: false,
+                })
+            }
+            status => Err(format!(
+                "Failed to fetch {}: status code {}",
+                endpoint, status
+            )),
+        }
+    }
+}
@@ -0,0 +1,121 @@
+use std::time::{Duration, Instant};
+
+use jsonwebtoken::jwk::JwkSet;
+use jsonwebtoken::{decode, decode_header, Algorithm, DecodingKey, Validation};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Deserializer, Serialize};
+use tracing::info;
+
+use super::Esi;
+
+/* EVE SSO rotates its keys rarely; refetch once an hour, or when a token uses an unknown key. */
+const JWKS_REFRESH: Duration = Duration::from_secs(60 * 60);
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct Claims {
+    pub sub: String,
+    pub name: String,
+    #[serde(default, deserialize_with = "deserialize_scopes")]
+    pub scp: Vec<String>,
+}
+
+/* EVE SSO uses a plain string when only a single scope is granted, and a list otherwise. */
+fn deserialize_scopes<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<String>, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Scopes {
+        One(String),
+        Many(Vec<String>),
+    }
+
+    match Scopes::deserialize(deserializer)? {
+        Scopes::One(scope) => Ok(vec![scope]),
+        Scopes::Many(scopes) => Ok(scopes),
+    }
+}
+
+#[derive(Deserialize)]
+struct SsoMetadata {
+    jwks_uri: String,
+}
+
+pub struct JwksCache {
+    jwks: JwkSet,
+    fetched: Instant,
+}
+
+impl Esi {
+    async fn fetch_jwks(&self) -> Result<JwkSet, String> {
+        let jwks_url = match &self.jwks_url {
+            Some(jwks_url) => jwks_url.clone(),
+            None => {
+                let metadata: SsoMetadata = self
+                    .get_json(&format!(
+                        "{}/.well-known/oauth-authorization-server",
+                        self.sso_base_url
+                    ))
+                    .await?;
+                metadata.jwks_uri
+            }
+        };
+
+        info!("Fetching EVE SSO key set from {}", jwks_url);
+
+        self.get_json(&jwks_url).await
+    }
+
+    async fn get_json<T: DeserializeOwned>(&self, url: &str) -> Result<T, String> {
+        let response = self.http.get(url).send().await.map_err(|e| e.to_string())?;
+
+        let status = response.status().as_u16();
+        let body = response.text().await.map_err(|e| e.to_string())?;
+
+        match status {
+            200 => serde_json::from_str(&body).map_err(|e| e.to_string()),
+            status => Err(format!("Failed to fetch {}: status code {}", url, status)),
+        }
+    }
+
+    async fn decoding_key(&self, kid: &str) -> Result<DecodingKey, String> {
+        let mut jwks_cache = self.jwks_cache.lock().await;
+
+        let stale = match jwks_cache.as_ref() {
+            Some(cache) => cache.fetched.elapsed() > JWKS_REFRESH || cache.jwks.find(kid).is_none(),
+            None => true,
+        };
+        if stale {
+            *jwks_cache = Some(JwksCache {
+                jwks: self.fetch_jwks().await?,
+                fetched: Instant::now(),
+            });
+        }
+
+        let jwk = jwks_cache
+            .as_ref()
+            .unwrap()
+            .jwks
+            .find(kid)
+            .ok_or(format!("Unknown key ID {}", kid))?;
+
+        DecodingKey::from_jwk(jwk).map_err(|e| e.to_string())
+    }
+
+    /// Validate an access token issued by EVE SSO, and return its claims.
+    pub async fn verify_jwt(&self, token: &str) -> Result<Claims, String> {
+        let header = decode_header(token).map_err(|e| e.to_string())?;
+        let kid = header.kid.ok_or("Token has no key ID".to_string())?;
+
+        let key = self.decoding_key(&kid).await?;
+
+        /* EVE SSO signs with RS256; never trust the algorithm from the token itself. */
+        let mut validation = Validation::new(Algorithm::RS256);
+        /* Tokens use either the bare host or the full URL of the SSO as issuer. */
+        let sso_host = self.sso_base_url.trim_start_matches("https://");
+        validation.set_issuer(&[sso_host, self.sso_base_url.as_str()]);
+        validation.set_audience(&[self.oauth.client_id().as_str()]);
+
+        decode::<Claims>(token, &key, &validation)
+            .map(|token| token.claims)
+            .map_err(|e| e.to_string())
+    }
+}
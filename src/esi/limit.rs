@@ -0,0 +1,114 @@
+use std::time::Duration;
+
+use rand::Rng;
+use tokio::time::Instant;
+use tracing::{info, warn};
+
+use super::Esi;
+
+/* Stop sending requests once this few errors are left in the current window. */
+const ERROR_LIMIT_MINIMUM: i64 = 10;
+const MAX_RETRIES: u32 = 3;
+const BACKOFF_BASE: Duration = Duration::from_secs(1);
+
+/// What ESI told us about our error budget in its last response.
+#[derive(Default)]
+pub struct ErrorLimit {
+    remain: Option<i64>,
+    reset: Option<Instant>,
+}
+
+fn header_as_i64(response: &reqwest::Response, name: &str) -> Option<i64> {
+    response.headers().get(name)?.to_str().ok()?.parse().ok()
+}
+
+fn backoff(attempt: u32) -> Duration {
+    let jitter = rand::thread_rng().gen_range(0..1000);
+    BACKOFF_BASE * 2u32.pow(attempt) + Duration::from_millis(jitter)
+}
+
+impl Esi {
+    /// Send a request to ESI, respecting the error budget and retrying on server errors.
+    pub(super) async fn send(
+        &self,
+        request: reqwest::RequestBuilder,
+    ) -> Result<reqwest::Response, String> {
+        let mut attempt = 0;
+
+        loop {
+            self.wait_for_error_limit().await;
+
+            let response = request
+                .try_clone()
+                .expect("ESI requests should be cloneable")
+                .send()
+                .await;
+
+            let error = match response {
+                Ok(response) => {
+                    self.update_error_limit(&response).await;
+
+                    let status = response.status().as_u16();
+                    if status != 420 && status < 500 {
+                        return Ok(response);
+                    }
+                    format!("status code {}", status)
+                }
+                Err(error) => error.to_string(),
+            };
+
+            if attempt >= MAX_RETRIES {
+                return Err(error);
+            }
+
+            let delay = backoff(attempt);
+            attempt += 1;
+            warn!(
+                "ESI request failed ({}); retrying in {:.1}s (attempt {} / {})",
+                error,
+                delay.as_secs_f32(),
+                attempt,
+                MAX_RETRIES
+            );
+            tokio::time::sleep(delay).await;
+        }
+    }
+
+    async fn update_error_limit(&self, response: &reqwest::Response) {
+        let remain = header_as_i64(response, "X-ESI-Error-Limit-Remain");
+        let reset = header_as_i64(response, "X-ESI-Error-Limit-Reset");
+
+        let mut error_limit = self.error_limit.lock().await;
+
+        /* A 420 means we already ran out; don't wait for the headers to tell us. */
+        error_limit.remain = match response.status().as_u16() {
+            420 => Some(0),
+            _ => remain.or(error_limit.remain),
+        };
+        if let Some(reset) = reset {
+            error_limit.reset = Some(Instant::now() + Duration::from_secs(reset as u64));
+        }
+    }
+
+    async fn wait_for_error_limit(&self) {
+        /* Holding the lock while sleeping pauses all other ESI traffic too. */
+        let mut error_limit = self.error_limit.lock().await;
+
+        let (Some(remain), Some(reset)) = (error_limit.remain, error_limit.reset) else {
+            return;
+        };
+        if remain > ERROR_LIMIT_MINIMUM || reset <= Instant::now() {
+            return;
+        }
+
+        warn!(
+            "ESI error limit almost reached ({} errors left); pausing all ESI requests for {}s",
+            remain,
+            (reset - Instant::now()).as_secs()
+        );
+        tokio::time::sleep_until(reset).await;
+        info!("ESI error limit reset; resuming ESI requests");
+
+        *error_limit = ErrorLimit::default();
+    }
+}
@@ -0,0 +1,260 @@
+use std::env;
+use std::{collections::HashMap, sync::Arc};
+
+use chrono::Utc;
+use oauth2::{
+    basic::BasicClient, reqwest::async_http_client, AuthUrl, AuthorizationCode, ClientId,
+    ClientSecret, CsrfToken, RedirectUrl, RefreshToken, Scope, TokenResponse, TokenUrl,
+};
+use serde::{Deserialize, Serialize};
+use tokio::sync::Mutex;
+
+mod cache;
+mod jwt;
+mod limit;
+mod names;
+mod sde;
+
+pub use jwt::Claims;
+
+/// Scopes we request from EVE SSO, and require to be granted.
+pub const REQUIRED_SCOPES: [&str; 2] =
+    ["esi-skills.read_skills.v1", "esi-skills.read_skillqueue.v1"];
+
+/// Everything needed to talk to EVE SSO and ESI.
+#[derive(Clone, Debug)]
+pub struct EsiConfig {
+    pub client_id: String,
+    pub client_secret: String,
+    pub webserver_url: String,
+    pub esi_base_url: String,
+    pub sso_base_url: String,
+    /// Normally discovered from the SSO metadata; can be set to use a local key set.
+    pub jwks_url: Option<String>,
+    pub storage_folder: Option<String>,
+    pub sde_folder: Option<String>,
+}
+
+impl EsiConfig {
+    pub fn from_env() -> Self {
+        Self {
+            client_id: env::var("EVE_CLIENT_ID")
+                .expect("Expected EVE_CLIENT_ID in the environment"),
+            client_secret: env::var("EVE_CLIENT_SECRET")
+                .expect("Expected EVE_CLIENT_SECRET in the environment"),
+            webserver_url: env::var("WEBSERVER_URL")
+                .expect("Expected WEBSERVER_URL in the environment"),
+            esi_base_url: env::var("ESI_BASE_URL").unwrap_or("https://esi.evetech.net".to_string()),
+            sso_base_url: env::var("SSO_BASE_URL")
+                .unwrap_or("https://login.eveonline.com".to_string()),
+            jwks_url: env::var("SSO_JWKS_URL").ok(),
+            storage_folder: env::var("STORAGE_FOLDER").ok(),
+            sde_folder: env::var("SDE_FOLDER").ok(),
+        }
+    }
+}
+
+pub struct Esi {
+    oauth: BasicClient,
+    client_secret: ClientSecret,
+    http: reqwest::Client,
+    esi_base_url: String,
+    sso_base_url: String,
+    skill_name_cache: Arc<Mutex<HashMap<i32, String>>>,
+    skill_name_path: Option<String>,
+    cache: Arc<Mutex<cache::EsiCache>>,
+    error_limit: Arc<Mutex<limit::ErrorLimit>>,
+    jwks_url: Option<String>,
+    jwks_cache: Arc<Mutex<Option<jwt::JwksCache>>>,
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct EsiSkill {
+    pub skill_id: i32,
+    pub skillpoints_in_skill: i32,
+    pub trained_skill_level: i32,
+    pub active_skill_level: i32,
+}
+
+#[derive(Debug, Deserialize)]
+#[allow(dead_code)]
+pub struct EsiSkills {
+    pub skills: Vec<EsiSkill>,
+    pub total_sp: i64,
+    pub unallocated_sp: i32,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[allow(dead_code)]
+pub struct EsiSkillQueueItem {
+    pub finish_date: Option<chrono::DateTime<Utc>>,
+    pub finished_level: i32,
+    pub level_end_sp: i32,
+    pub level_start_sp: i32,
+    pub queue_position: i32,
+    pub skill_id: i32,
+    pub start_date: Option<chrono::DateTime<Utc>>,
+    pub training_start_sp: i32,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct EsiSkillQueue(pub Vec<EsiSkillQueueItem>);
+
+#[derive(Debug)]
+pub struct EsiResponse<T> {
+    pub data: T,
+    /// When ESI will refresh its cache; polling before this returns the same data.
+    pub expires: Option<chrono::DateTime<Utc>>,
+    /// False if ESI answered "304 Not Modified"; `data` is then the previous response.
+    pub modified: bool,
+}
+
+impl Esi {
+    pub fn new(config: EsiConfig) -> Self {
+        /* Skill names are persisted, and optionally seeded from the SDE, to avoid looking them up on ESI. */
+        let skill_name_path = config
+            .storage_folder
+            .map(|storage_folder| format!("{}/skill-names.json", storage_folder));
+        let mut skill_name_cache = match &skill_name_path {
+            Some(path) => names::load_skill_names(path),
+            None => HashMap::new(),
+        };
+        if let Some(sde_folder) = config.sde_folder {
+            let skill_types =
+                sde::load_skill_types(&sde_folder).expect("Failed to load SDE from SDE_FOLDER");
+            skill_name_cache.extend(skill_types);
+        }
+
+        let oauth_client = BasicClient::new(
+            ClientId::new(config.client_id),
+            Some(ClientSecret::new(config.client_secret.clone())),
+            AuthUrl::new(format!("{}/v2/oauth/authorize", config.sso_base_url))
+                .expect("Invalid authorization URL"),
+            Some(
+                TokenUrl::new(format!("{}/v2/oauth/token", config.sso_base_url))
+                    .expect("Invalid token URL"),
+            ),
+        )
+        .set_redirect_uri(
+            RedirectUrl::new(format!("{}/callback", config.webserver_url))
+                .expect("Invalid redirect URL"),
+        );
+
+        Esi {
+            oauth: oauth_client,
+            client_secret: ClientSecret::new(config.client_secret),
+            http: reqwest::Client::new(),
+            esi_base_url: config.esi_base_url,
+            sso_base_url: config.sso_base_url,
+            skill_name_cache: Arc::new(Mutex::new(skill_name_cache)),
+            skill_name_path,
+            cache: Arc::new(Mutex::new(cache::EsiCache::default())),
+            error_limit: Arc::new(Mutex::new(limit::ErrorLimit::default())),
+            jwks_url: config.jwks_url,
+            jwks_cache: Arc::new(Mutex::new(None)),
+        }
+    }
+
+    pub async fn exchange_code(&self, code: String) -> Result<(String, String), String> {
+        let token = self
+            .oauth
+            .exchange_code(AuthorizationCode::new(code))
+            .request_async(async_http_client)
+            .await;
+
+        if let Ok(token) = token {
+            Ok((
+                token.access_token().secret().to_string(),
+                token.refresh_token().unwrap().secret().to_string(),
+            ))
+        } else {
+            Err("Failed to exchange code".to_string())
+        }
+    }
+
+    pub async fn exchange_refresh_token(
+        &self,
+        refresh_token: String,
+    ) -> Result<(String, String), String> {
+        let token = self
+            .oauth
+            .exchange_refresh_token(&RefreshToken::new(refresh_token))
+            .request_async(async_http_client)
+            .await;
+
+        if let Ok(token) = token {
+            Ok((
+                token.access_token().secret().to_string(),
+                token.refresh_token().unwrap().secret().to_string(),
+            ))
+        } else {
+            Err("Failed to exchange refresh token".to_string())
+        }
+    }
+
+    pub async fn revoke_refresh_token(&self, refresh_token: String) -> Result<(), String> {
+        /* The oauth2 crate only revokes over HTTPS; do it ourselves, so a local SSO works too. */
+        let response = self
+            .http
+            .post(format!("{}/v2/oauth/revoke", self.sso_base_url))
+            .basic_auth(
+                self.oauth.client_id().as_str(),
+                Some(self.client_secret.secret()),
+            )
+            .form(&[
+                ("token_type_hint", "refresh_token"),
+                ("token", refresh_token.as_str()),
+            ])
+            .send()
+            .await
+            .map_err(|e| e.to_string())?;
+
+        match response.status().as_u16() {
+            200 => Ok(()),
+            status => Err(format!(
+                "Failed to revoke refresh token: status code {}",
+                status
+            )),
+        }
+    }
+
+    pub async fn get_authorization_url(&self, state: String) -> String {
+        let state = CsrfToken::new(state);
+
+        let (auth_url, _) = self
+            .oauth
+            .authorize_url(|| state)
+            .add_scopes(REQUIRED_SCOPES.map(|scope| Scope::new(scope.to_string())))
+            .url();
+
+        auth_url.to_string()
+    }
+
+    pub async fn get_skills(
+        &self,
+        access_token: &str,
+        character_id: u64,
+    ) -> Result<EsiResponse<EsiSkills>, String> {
+        let url = format!(
+            "{}/v4/characters/{}/skills/",
+            self.esi_base_url, character_id
+        );
+
+        self.get_cached(access_token, character_id, "skills", &url)
+            .await
+    }
+
+    pub async fn get_skill_queue(
+        &self,
+        access_token: &str,
+        character_id: u64,
+    ) -> Result<EsiResponse<EsiSkillQueue>, String> {
+        let url = format!(
+            "{}/v2/characters/{}/skillqueue/",
+            self.esi_base_url, character_id
+        );
+
+        self.get_cached(access_token, character_id, "skill queue", &url)
+            .await
+    }
+}
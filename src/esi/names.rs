@@ -0,0 +1,137 @@
+use std::collections::HashMap;
+
+use serde::Deserialize;
+use tracing::{info, warn};
+
+use crate::monitor::write_atomic;
+
+use super::Esi;
+
+/* ESI accepts at most this many IDs per /universe/names/ request. */
+const MAX_IDS_PER_REQUEST: usize = 1000;
+
+#[derive(Debug, Deserialize)]
+#[allow(dead_code)]
+struct EsiNamesLookup {
+    id: i32,
+    category: String,
+    name: String,
+}
+
+/// Load previously looked-up skill names, so a restart doesn't look them all up again.
+pub fn load_skill_names(path: &str) -> HashMap<i32, String> {
+    let Ok(names) = std::fs::read_to_string(path) else {
+        return HashMap::new();
+    };
+
+    match serde_json::from_str::<HashMap<i32, String>>(&names) {
+        Ok(names) => {
+            info!("Loaded {} skill names from {}", names.len(), path);
+            names
+        }
+        Err(error) => {
+            warn!("Ignoring invalid skill name cache {}: {}", path, error);
+            HashMap::new()
+        }
+    }
+}
+
+impl Esi {
+    pub async fn cached_skill_name(&self, skill_id: i32) -> Option<String> {
+        let skill_name_cache = self.skill_name_cache.lock().await;
+
+        skill_name_cache.get(&skill_id).cloned()
+    }
+
+    /// Look up the names of all given skills, with a single request for those not known yet.
+    pub async fn lookup_skill_names(
+        &self,
+        skill_ids: &[i32],
+    ) -> Result<HashMap<i32, String>, String> {
+        /* Don't hold the cache while talking to ESI; that would block anyone only wanting cached names. */
+        let mut unknown: Vec<i32> = {
+            let skill_name_cache = self.skill_name_cache.lock().await;
+
+            skill_ids
+                .iter()
+                .filter(|skill_id| !skill_name_cache.contains_key(skill_id))
+                .cloned()
+                .collect()
+        };
+        unknown.sort();
+        unknown.dedup();
+
+        let mut names = HashMap::new();
+        let mut result = Ok(());
+
+        /* ESI fails the whole request if a single ID is invalid; split it up till those are found. */
+        let mut batches: Vec<&[i32]> = unknown.chunks(MAX_IDS_PER_REQUEST).collect();
+        while let Some(batch) = batches.pop() {
+            match self.fetch_names(batch).await {
+                Ok(Some(lookups)) => {
+                    names.extend(lookups.into_iter().map(|lookup| (lookup.id, lookup.name)))
+                }
+                Ok(None) if batch.len() > 1 => {
+                    let (first, second) = batch.split_at(batch.len() / 2);
+                    batches.push(first);
+                    batches.push(second);
+                }
+                Ok(None) => warn!("Skill {} is unknown to ESI", batch[0]),
+                Err(error) => {
+                    result = Err(error);
+                    break;
+                }
+            }
+        }
+
+        let mut skill_name_cache = self.skill_name_cache.lock().await;
+        if !names.is_empty() {
+            skill_name_cache.extend(names);
+
+            if let Some(path) = &self.skill_name_path {
+                if let Err(error) =
+                    write_atomic(path, &serde_json::to_string(&*skill_name_cache).unwrap())
+                {
+                    warn!("Failed to write skill name cache {}: {}", path, error);
+                }
+            }
+        }
+        result?;
+
+        Ok(skill_ids
+            .iter()
+            .filter_map(|skill_id| {
+                skill_name_cache
+                    .get(skill_id)
+                    .map(|name| (*skill_id, name.clone()))
+            })
+            .collect())
+    }
+
+    /* None if ESI doesn't know one of the IDs. */
+    async fn fetch_names(&self, ids: &[i32]) -> Result<Option<Vec<EsiNamesLookup>>, String> {
+        let url = format!("{}/v3/universe/names/", self.esi_base_url);
+
+        let response = self
+            .send(
+                self.http
+                    .post(&url)
+                    .body(serde_json::to_string(ids).unwrap())
+                    .header("Content-Type", "application/json"),
+            )
+            .await?;
+
+        let status = response.status().as_u16();
+        let body = response.text().await.map_err(|e| e.to_string())?;
+        match status {
+            200 => Ok(Some(
+                serde_json::from_str(&body).map_err(|e| e.to_string())?,
+            )),
+            404 => Ok(None),
+            status => Err(format!(
+                "Failed to fetch skill names: status code {}",
+                status
+            )),
+        }
+    }
+}
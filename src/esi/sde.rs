@@ -0,0 +1,67 @@
+use std::collections::{HashMap, HashSet};
+use std::io::{BufRead, BufReader};
+
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+use tracing::info;
+
+/* Category of all skill groups in the SDE. */
+const CATEGORY_SKILL: i32 = 16;
+
+#[derive(Deserialize)]
+struct SdeName {
+    #[serde(default)]
+    en: String,
+}
+
+#[derive(Deserialize)]
+struct SdeGroup {
+    #[serde(rename = "_key")]
+    key: i32,
+    #[serde(rename = "categoryID")]
+    category_id: i32,
+}
+
+#[derive(Deserialize)]
+struct SdeType {
+    #[serde(rename = "_key")]
+    key: i32,
+    #[serde(rename = "groupID")]
+    group_id: i32,
+    name: SdeName,
+}
+
+fn read_jsonl<T: DeserializeOwned>(folder: &str, name: &str) -> Result<Vec<T>, String> {
+    let path = format!("{}/{}", folder, name);
+    let file = std::fs::File::open(&path).map_err(|e| format!("{}: {}", path, e))?;
+
+    let mut entries = Vec::new();
+    for line in BufReader::new(file).lines() {
+        let line = line.map_err(|e| format!("{}: {}", path, e))?;
+        if line.is_empty() {
+            continue;
+        }
+        entries.push(serde_json::from_str(&line).map_err(|e| format!("{}: {}", path, e))?);
+    }
+
+    Ok(entries)
+}
+
+/// Load the names of all skills from an (extracted) SDE in JSON Lines format, as published by CCP.
+pub fn load_skill_types(folder: &str) -> Result<HashMap<i32, String>, String> {
+    let groups: HashSet<i32> = read_jsonl::<SdeGroup>(folder, "groups.jsonl")?
+        .into_iter()
+        .filter(|g| g.category_id == CATEGORY_SKILL)
+        .map(|g| g.key)
+        .collect();
+
+    let skill_types: HashMap<i32, String> = read_jsonl::<SdeType>(folder, "types.jsonl")?
+        .into_iter()
+        .filter(|t| groups.contains(&t.group_id))
+        .map(|t| (t.key, t.name.en))
+        .collect();
+
+    info!("Loaded {} skills from SDE in {}", skill_types.len(), folder);
+
+    Ok(skill_types)
+}
@@ -0,0 +1,6 @@
+pub mod discord;
+pub mod esi;
+pub mod monitor;
+pub mod notifier;
+pub mod state;
+pub mod webserver;
@@ -1,19 +1,52 @@
 use std::env;
 
+use eve_skills_discord::esi::{Esi, EsiConfig};
+use eve_skills_discord::monitor::{rotate_storage_key, StorageConfig, StorageKey};
+use eve_skills_discord::{state, webserver};
 use serenity::all::GatewayIntents;
 use serenity::Client;
 use tracing::error;
 
-mod discord;
-mod esi;
-mod monitor;
-mod state;
-mod webserver;
+/* Maintenance commands; without a command, the bot is started. */
+fn run_command(command: &str, args: &[String]) -> Result<(), String> {
+    match command {
+        "generate-key" => {
+            println!("{}", StorageKey::generate());
+            Ok(())
+        }
+        "rotate-key" => {
+            let new_key_file = args
+                .first()
+                .ok_or("Usage: rotate-key <file with the new key>")?;
+            let new_key = StorageKey::from_file(new_key_file)?;
+
+            let rotated = rotate_storage_key(&StorageConfig::from_env()?, new_key)?;
+            println!(
+                "Re-encrypted {} characters. Now point STORAGE_KEY_FILE to {} (or set STORAGE_KEY to its content).",
+                rotated, new_key_file
+            );
+            Ok(())
+        }
+        command => Err(format!("Unknown command {}", command)),
+    }
+}
 
 #[tokio::main]
 async fn main() {
     /* Load, if it exists, from the .env file. This mostly makes development easier. */
     let _ = dotenv::dotenv();
+
+    let args: Vec<String> = env::args().skip(1).collect();
+    if let Some(command) = args.first() {
+        tracing_subscriber::fmt::init();
+
+        if let Err(error) = run_command(command, &args[1..]) {
+            eprintln!("{}", error);
+            std::process::exit(1);
+        }
+        return;
+    }
+
     let discord_token =
         env::var("DISCORD_TOKEN").expect("Expected DISCORD_TOKEN in the environment");
 
@@ -24,8 +57,10 @@ async fn main() {
         | GatewayIntents::DIRECT_MESSAGES
         | GatewayIntents::MESSAGE_CONTENT;
 
-    let bot =
-        state::BotState::new("Authentication timed out. Use /monitor to try again.".to_string());
+    let bot = state::BotState::new(
+        "Authentication timed out. Use /monitor to try again.".to_string(),
+        Esi::new(EsiConfig::from_env()),
+    );
 
     let web_app = webserver::create_app(bot.clone());
     let web_listener = tokio::net::TcpListener::bind("0.0.0.0:3000").await.unwrap();
@@ -0,0 +1,139 @@
+use crate::esi::EsiSkill;
+
+use super::{format_sp, level_to_roman};
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum SkillChange {
+    Finished {
+        skill_id: i32,
+        level: i32,
+    },
+    Injected {
+        skill_id: i32,
+    },
+    /* SP removed with a skill extractor, without losing a level. */
+    Extracted {
+        skill_id: i32,
+        sp_removed: i64,
+    },
+    LevelLost {
+        skill_id: i32,
+        old_level: i32,
+        new_level: i32,
+        sp_removed: i64,
+    },
+    /* All SP extracted; the skill is gone. */
+    Removed {
+        skill_id: i32,
+        sp_removed: i64,
+    },
+}
+
+impl SkillChange {
+    pub fn skill_id(&self) -> i32 {
+        match self {
+            SkillChange::Finished { skill_id, .. }
+            | SkillChange::Injected { skill_id }
+            | SkillChange::Extracted { skill_id, .. }
+            | SkillChange::LevelLost { skill_id, .. }
+            | SkillChange::Removed { skill_id, .. } => *skill_id,
+        }
+    }
+
+    /// The line posted in the activity thread for this change.
+    pub fn describe(&self, skill_name: &str) -> String {
+        match *self {
+            SkillChange::Finished { level, .. } => format!(
+                "`{} {}` has finished training.\n",
+                skill_name,
+                level_to_roman(level),
+            ),
+            SkillChange::Injected { .. } => format!("`{}` has been injected.\n", skill_name),
+            SkillChange::Extracted { sp_removed, .. } => format!(
+                "{} SP has been extracted from `{}`.\n",
+                format_sp(sp_removed),
+                skill_name,
+            ),
+            /* Some SP can be left in a skill without any level trained. */
+            SkillChange::LevelLost {
+                old_level,
+                new_level: 0,
+                sp_removed,
+                ..
+            } => format!(
+                "`{}` has dropped from level {} to untrained; {} SP has been extracted.\n",
+                skill_name,
+                level_to_roman(old_level),
+                format_sp(sp_removed),
+            ),
+            SkillChange::LevelLost {
+                old_level,
+                new_level,
+                sp_removed,
+                ..
+            } => format!(
+                "`{}` has dropped from level {} to {}; {} SP has been extracted.\n",
+                skill_name,
+                level_to_roman(old_level),
+                level_to_roman(new_level),
+                format_sp(sp_removed),
+            ),
+            SkillChange::Removed { sp_removed, .. } => format!(
+                "`{}` has been removed; {} SP has been extracted.\n",
+                skill_name,
+                format_sp(sp_removed),
+            ),
+        }
+    }
+}
+
+/// Everything that changed between two versions of a character's skills.
+pub fn skills_diff(old_skills: &[EsiSkill], new_skills: &[EsiSkill]) -> Vec<SkillChange> {
+    let mut changes = Vec::new();
+
+    for new_skill in new_skills {
+        let skill_id = new_skill.skill_id;
+        let old_skill = old_skills.iter().find(|s| s.skill_id == skill_id);
+
+        let Some(old_skill) = old_skill else {
+            changes.push(match new_skill.trained_skill_level {
+                0 => SkillChange::Injected { skill_id },
+                level => SkillChange::Finished { skill_id, level },
+            });
+            continue;
+        };
+
+        let sp_removed =
+            old_skill.skillpoints_in_skill as i64 - new_skill.skillpoints_in_skill as i64;
+
+        if new_skill.trained_skill_level > old_skill.trained_skill_level {
+            changes.push(SkillChange::Finished {
+                skill_id,
+                level: new_skill.trained_skill_level,
+            });
+        } else if new_skill.trained_skill_level < old_skill.trained_skill_level {
+            changes.push(SkillChange::LevelLost {
+                skill_id,
+                old_level: old_skill.trained_skill_level,
+                new_level: new_skill.trained_skill_level,
+                sp_removed,
+            });
+        } else if sp_removed > 0 {
+            changes.push(SkillChange::Extracted {
+                skill_id,
+                sp_removed,
+            });
+        }
+    }
+
+    for old_skill in old_skills {
+        if !new_skills.iter().any(|s| s.skill_id == old_skill.skill_id) {
+            changes.push(SkillChange::Removed {
+                skill_id: old_skill.skill_id,
+                sp_removed: old_skill.skillpoints_in_skill as i64,
+            });
+        }
+    }
+
+    changes
+}
@@ -0,0 +1,304 @@
+use chrono::{DateTime, Datelike, TimeDelta, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::esi::{EsiSkill, EsiSkillQueueItem};
+
+use super::diff::{skills_diff, SkillChange};
+use super::{format_sp, level_to_roman, storage::StorageV2, Monitor};
+
+/* Keep a bit more than the longest report period. */
+const HISTORY_RETENTION: TimeDelta = TimeDelta::days(35);
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct SpSample {
+    pub time: DateTime<Utc>,
+    pub total_sp: i64,
+    pub unallocated_sp: i32,
+}
+
+/// A stretch of time in which a skill trained at a constant rate, as the skill queue predicted.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct TrainingSpan {
+    pub start: DateTime<Utc>,
+    pub end: DateTime<Utc>,
+    pub sp_per_hour: f64,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct FinishedSkill {
+    pub time: DateTime<Utc>,
+    pub skill_id: i32,
+    pub level: i32,
+}
+
+/// Skill points over time, and which skills finished when.
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct SpHistory {
+    /* Only stored when something changed; between samples, the previous value holds. */
+    pub samples: Vec<SpSample>,
+    /* ESI only updates the total SP when a level completes; what is trained in between comes
+     * from the skill queue. Only the past is kept, plus the queue as last seen. */
+    #[serde(default)]
+    pub training: Vec<TrainingSpan>,
+    pub finished: Vec<FinishedSkill>,
+    pub last_report: Option<DateTime<Utc>>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ReportPeriod {
+    Off,
+    Daily,
+    Weekly,
+}
+
+pub struct Summary<'a> {
+    pub sp_trained: i64,
+    pub sp_per_hour: f64,
+    pub finished: Vec<&'a FinishedSkill>,
+}
+
+impl ReportPeriod {
+    pub fn parse(period: &str) -> Result<Self, String> {
+        match period {
+            "off" => Ok(ReportPeriod::Off),
+            "daily" => Ok(ReportPeriod::Daily),
+            "weekly" => Ok(ReportPeriod::Weekly),
+            period => Err(format!("Unknown report period {}", period)),
+        }
+    }
+
+    fn duration(&self) -> TimeDelta {
+        match self {
+            ReportPeriod::Off => TimeDelta::zero(),
+            ReportPeriod::Daily => TimeDelta::days(1),
+            ReportPeriod::Weekly => TimeDelta::weeks(1),
+        }
+    }
+
+    /// Start of the period `time` is in: midnight UTC, or Monday midnight UTC for weekly reports.
+    fn start_of(&self, time: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        let midnight = time.date_naive().and_hms_opt(0, 0, 0)?.and_utc();
+
+        match self {
+            ReportPeriod::Off => None,
+            ReportPeriod::Daily => Some(midnight),
+            ReportPeriod::Weekly => {
+                Some(midnight - TimeDelta::days(time.weekday().num_days_from_monday() as i64))
+            }
+        }
+    }
+
+    fn name(&self) -> &'static str {
+        match self {
+            ReportPeriod::Off => "",
+            ReportPeriod::Daily => "Daily",
+            ReportPeriod::Weekly => "Weekly",
+        }
+    }
+}
+
+impl SpHistory {
+    pub fn record(&mut self, time: DateTime<Utc>, total_sp: i64, unallocated_sp: i32) {
+        let unchanged = self
+            .samples
+            .last()
+            .is_some_and(|s| s.total_sp == total_sp && s.unallocated_sp == unallocated_sp);
+        if !unchanged {
+            self.samples.push(SpSample {
+                time,
+                total_sp,
+                unallocated_sp,
+            });
+        }
+
+        /* Keep the last sample before the cutoff; it holds the value at the cutoff. */
+        let cutoff = time - HISTORY_RETENTION;
+        let before_cutoff = self.samples.iter().filter(|s| s.time < cutoff).count();
+        if before_cutoff > 1 {
+            self.samples.drain(..before_cutoff - 1);
+        }
+        self.training.retain(|t| t.end >= cutoff);
+        self.finished.retain(|f| f.time >= cutoff);
+    }
+
+    /// Replace what the previous skill queue predicted from `time` on, by the current one.
+    pub fn record_training(&mut self, time: DateTime<Utc>, skill_queue: &[EsiSkillQueueItem]) {
+        self.training.retain(|t| t.start < time);
+        if let Some(last) = self.training.last_mut() {
+            last.end = last.end.min(time);
+        }
+
+        for queue in skill_queue {
+            /* A paused queue has no dates; nothing trains. */
+            let (Some(start), Some(end)) = (queue.start_date, queue.finish_date) else {
+                continue;
+            };
+            let hours = (end - start).num_seconds() as f64 / 3600.0;
+            if end <= time || hours <= 0.0 {
+                continue;
+            }
+            let sp_per_hour = (queue.level_end_sp - queue.training_start_sp) as f64 / hours;
+            let start = start.max(time);
+
+            /* Most refreshes see the same skill training; keep that a single span. */
+            match self.training.last_mut() {
+                Some(last) if last.end == start && last.sp_per_hour == sp_per_hour => {
+                    last.end = end;
+                }
+                _ => self.training.push(TrainingSpan {
+                    start,
+                    end,
+                    sp_per_hour,
+                }),
+            }
+        }
+    }
+
+    pub fn record_finished(
+        &mut self,
+        time: DateTime<Utc>,
+        old_skills: &[EsiSkill],
+        new_skills: &[EsiSkill],
+    ) {
+        for change in skills_diff(old_skills, new_skills) {
+            if let SkillChange::Finished { skill_id, level } = change {
+                self.finished.push(FinishedSkill {
+                    time,
+                    skill_id,
+                    level,
+                });
+            }
+        }
+    }
+
+    fn sample_at(&self, time: DateTime<Utc>) -> Option<&SpSample> {
+        self.samples.iter().rev().find(|s| s.time <= time)
+    }
+
+    /// What happened between `start` and `end`; None if the character wasn't monitored yet.
+    pub fn summary(&self, start: DateTime<Utc>, end: DateTime<Utc>) -> Option<Summary<'_>> {
+        self.sample_at(end)?;
+        /* A character added halfway through only counts from when it was added. */
+        let start = start.max(self.samples.first()?.time);
+
+        let hours = |from: DateTime<Utc>, to: DateTime<Utc>| {
+            (to - from).num_seconds().max(0) as f64 / 3600.0
+        };
+        let sp_trained: f64 = self
+            .training
+            .iter()
+            .map(|t| t.sp_per_hour * hours(t.start.max(start), t.end.min(end)))
+            .sum();
+        let hours = hours(start, end);
+
+        Some(Summary {
+            sp_trained: sp_trained.round() as i64,
+            sp_per_hour: if hours > 0.0 { sp_trained / hours } else { 0.0 },
+            finished: self
+                .finished
+                .iter()
+                .filter(|f| f.time >= start && f.time < end)
+                .collect(),
+        })
+    }
+}
+
+/// A message if the unallocated SP changed, with its most likely cause.
+pub fn unallocated_sp_change(
+    old_total_sp: i64,
+    old_unallocated_sp: i32,
+    new_total_sp: i64,
+    new_unallocated_sp: i32,
+) -> Option<String> {
+    let change = new_unallocated_sp as i64 - old_unallocated_sp as i64;
+    let now = format_sp(new_unallocated_sp as i64);
+
+    if change > 0 {
+        Some(format!(
+            "Unallocated SP went up by {} (now {}); likely a skill injector or a reward.\n",
+            format_sp(change),
+            now
+        ))
+    } else if change < 0 && new_total_sp - old_total_sp >= -change {
+        Some(format!(
+            "Unallocated SP went down by {} (now {}); likely applied to skills.\n",
+            format_sp(-change),
+            now
+        ))
+    } else if change < 0 {
+        Some(format!(
+            "Unallocated SP went down by {} (now {}).\n",
+            format_sp(-change),
+            now
+        ))
+    } else {
+        None
+    }
+}
+
+impl Monitor {
+    /// The summary of the last full period, if it wasn't reported yet.
+    ///
+    /// Once posted, the caller marks it as reported with `SpHistory::last_report`.
+    pub(super) async fn sp_report(
+        &self,
+        storage: &mut StorageV2,
+        now: DateTime<Utc>,
+    ) -> Option<String> {
+        let period_end = self.report_period.start_of(now)?;
+        let period_start = period_end - self.report_period.duration();
+
+        match storage.history.last_report {
+            /* Only report on periods the character was monitored for. */
+            None => {
+                storage.history.last_report = Some(now);
+                return None;
+            }
+            Some(last_report) if last_report >= period_end => return None,
+            Some(_) => {}
+        }
+
+        let Some(summary) = storage.history.summary(period_start, period_end) else {
+            /* Samples are only added at the current time; this period stays empty. */
+            storage.history.last_report = Some(now);
+            return None;
+        };
+
+        let skill_ids: Vec<i32> = summary.finished.iter().map(|f| f.skill_id).collect();
+        let skill_names = self.lookup_skill_names(&skill_ids).await;
+
+        let mut message = format!(
+            "**{} summary** (<t:{}:d> - <t:{}:d>)\n",
+            self.report_period.name(),
+            period_start.timestamp(),
+            (period_end - TimeDelta::seconds(1)).timestamp(),
+        );
+        message += &format!(
+            "- {} SP trained, {} SP/hour.\n",
+            format_sp(summary.sp_trained),
+            format_sp(summary.sp_per_hour.round() as i64),
+        );
+        message += &match summary.finished.len() {
+            0 => "- No skills finished.\n".to_string(),
+            1 => "- 1 skill finished:".to_string(),
+            count => format!("- {} skills finished:", count),
+        };
+        for (index, finished) in summary.finished.iter().enumerate() {
+            let skill_name = skill_names
+                .get(&finished.skill_id)
+                .map_or("Unknown", |name| name.as_str());
+
+            message += &format!(
+                "{} `{} {}`",
+                if index == 0 { "" } else { "," },
+                skill_name,
+                level_to_roman(finished.level),
+            );
+        }
+        if !summary.finished.is_empty() {
+            message += ".\n";
+        }
+
+        Some(message)
+    }
+}
@@ -0,0 +1,46 @@
+use super::{storage::StorageV2, Monitor};
+
+#[derive(Clone, Debug)]
+pub struct IndexEntry {
+    pub eve_character_id: u64,
+    pub eve_character_name: String,
+    pub discord_character_id: u64,
+    pub expired: bool,
+}
+
+impl From<&StorageV2> for IndexEntry {
+    fn from(storage: &StorageV2) -> Self {
+        Self {
+            eve_character_id: storage.eve_character_id,
+            eve_character_name: storage.eve_character_name.clone(),
+            discord_character_id: storage.discord_character_id,
+            expired: storage.expired,
+        }
+    }
+}
+
+impl Monitor {
+    pub fn index_update(&self, storage: &StorageV2) {
+        let mut index = self.index.lock().unwrap();
+        index.insert(storage.eve_character_id, IndexEntry::from(storage));
+    }
+
+    pub fn index_remove(&self, eve_character_id: u64) {
+        let mut index = self.index.lock().unwrap();
+        index.remove(&eve_character_id);
+    }
+
+    /// All characters (including expired ones) owned by the given Discord user, sorted by name.
+    pub fn index_find(&self, discord_character_id: u64) -> Vec<IndexEntry> {
+        let index = self.index.lock().unwrap();
+
+        let mut entries: Vec<IndexEntry> = index
+            .values()
+            .filter(|e| e.discord_character_id == discord_character_id)
+            .cloned()
+            .collect();
+        entries.sort_by(|a, b| a.eve_character_name.cmp(&b.eve_character_name));
+
+        entries
+    }
+}
@@ -1,43 +1,39 @@
+use chrono::Utc;
+use tracing::error;
+
 use super::{Character, Monitor};
 
 impl Monitor {
     pub async fn load_all_eve_characters(&self) {
-        let storage_files = std::fs::read_dir(&self.storage_folder).unwrap();
-
-        for storage_file in storage_files {
-            let storage_file = storage_file.unwrap();
-            let storage_file = storage_file.path();
-            let storage_file = storage_file.file_name().unwrap();
-
-            /* Characters are stored in char-<number>.json. */
-            if let Some(storage_file) = storage_file.to_str() {
-                if storage_file.starts_with("char-") && storage_file.ends_with(".json") {
-                    let eve_character_id: u64 =
-                        storage_file[5..storage_file.len() - 5].parse().unwrap();
-
-                    let storage = self.read_from_storage(eve_character_id).unwrap();
-                    if !storage.expired {
-                        let mut list = self.eve_character_list.lock().await;
-                        list.push(Character {
-                            id: eve_character_id,
-                            retries: 0,
-                        });
-                    }
+        for eve_character_id in self.list_storage() {
+            /* One unreadable character shouldn't stop all others from being monitored. */
+            let storage = match self.read_from_storage(eve_character_id) {
+                Ok(storage) => storage,
+                Err(error) => {
+                    error!("[{}] Not loading character: {}", eve_character_id, error);
+                    continue;
                 }
+            };
+            self.index_update(&storage);
+
+            if !storage.expired {
+                self.schedule_eve_character(Character {
+                    id: eve_character_id,
+                    retries: 0,
+                    next_update: Utc::now(),
+                })
+                .await;
             }
         }
     }
 
     pub async fn queue_eve_character(&self, eve_character_id: u64) {
-        let mut character = Character {
+        /* As this is a new entry, update the character immediately. */
+        self.schedule_eve_character(Character {
             id: eve_character_id,
             retries: 0,
-        };
-
-        /* As this is a new entry, update the character immediately. */
-        self.update_character(&mut character).await;
-
-        let mut list = self.eve_character_list.lock().await;
-        list.push(character);
+            next_update: Utc::now(),
+        })
+        .await;
     }
 }
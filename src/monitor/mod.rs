@@ -1,27 +1,102 @@
+use std::collections::{BinaryHeap, HashMap};
 use std::{env, sync::Arc};
 
-use tokio::sync::Mutex;
-use tracing::{info, warn};
+use chrono::{DateTime, TimeDelta, Utc};
+use tokio::sync::{Mutex, Notify, Semaphore};
+use tracing::{error, info, warn};
 
 use crate::{esi::EsiSkill, state::BotState};
 
+mod diff;
+mod history;
+mod index;
 mod install;
 mod load;
+mod pause;
+mod progress;
+mod schedule;
+mod status;
 mod storage;
-
+mod uninstall;
+mod warnings;
+
+pub use diff::{skills_diff, SkillChange};
+pub use history::{FinishedSkill, ReportPeriod, SpHistory, SpSample, TrainingSpan};
+pub use index::IndexEntry;
+pub use schedule::next_update;
+pub(crate) use storage::write_atomic;
+pub use storage::{
+    rotate_storage_key, JsonStorage, SqliteStorage, StorageBackend, StorageConfig, StorageKey,
+    StorageKind, StorageLock, StorageV1, StorageV2,
+};
+pub use uninstall::ChannelAction;
+pub use warnings::parse_thresholds;
+
+#[derive(Clone)]
 pub struct Character {
     id: u64,
     retries: u64,
+    next_update: DateTime<Utc>,
+}
+
+/// Settings of the skill monitor, normally taken from the environment.
+pub struct MonitorConfig {
+    pub storage: StorageConfig,
+    /* Category in which the private channels are created. */
+    pub discord_category_id: u64,
+    /* How many characters are refreshed in parallel. */
+    pub concurrency: usize,
+    /* How often to post a summary of the SP gained. */
+    pub report_period: ReportPeriod,
+    /* How long before the skill queue runs empty to warn the user. */
+    pub queue_warnings: Vec<TimeDelta>,
+}
+
+impl MonitorConfig {
+    pub fn from_env() -> Self {
+        Self {
+            storage: StorageConfig::from_env().unwrap_or_else(|error| panic!("{}", error)),
+            discord_category_id: env::var("DISCORD_CATEGORY_ID")
+                .expect("Expected DISCORD_CATEGORY_ID in the environment")
+                .parse()
+                .expect("DISCORD_CATEGORY_ID should be a number"),
+            concurrency: env::var("MONITOR_CONCURRENCY")
+                .map(|concurrency| {
+                    concurrency
+                        .parse()
+                        .expect("MONITOR_CONCURRENCY should be a number")
+                })
+                .unwrap_or(4),
+            report_period: env::var("SP_REPORT")
+                .map(|period| {
+                    ReportPeriod::parse(&period)
+                        .unwrap_or_else(|error| panic!("SP_REPORT: {}", error))
+                })
+                .unwrap_or(ReportPeriod::Daily),
+            queue_warnings: warnings::parse_thresholds(
+                &env::var("QUEUE_WARNINGS").unwrap_or("24h,1h".to_string()),
+            )
+            .unwrap_or_else(|error| panic!("QUEUE_WARNINGS: {}", error)),
+        }
+    }
 }
 
 pub struct Monitor {
     bot: BotState,
-    storage_folder: String,
-    eve_character_list: Arc<Mutex<Vec<Character>>>,
-    current_index: Arc<Mutex<usize>>,
+    storage: Box<dyn StorageBackend>,
+    /* Held as long as the monitor runs, so the key can't be rotated underneath it. */
+    _storage_lock: StorageLock,
+    discord_category_id: u64,
+    report_period: ReportPeriod,
+    queue_warnings: Vec<TimeDelta>,
+    eve_character_list: Arc<Mutex<HashMap<u64, Character>>>,
+    schedule: Arc<Mutex<schedule::Schedule>>,
+    schedule_changed: Notify,
+    workers: Arc<Semaphore>,
+    index: std::sync::Mutex<HashMap<u64, index::IndexEntry>>,
 }
 
-fn level_to_roman(level: i32) -> &'static str {
+pub fn level_to_roman(level: i32) -> &'static str {
     match level {
         1 => "I",
         2 => "II",
@@ -32,21 +107,46 @@ fn level_to_roman(level: i32) -> &'static str {
     }
 }
 
-impl Monitor {
-    pub fn new(bot: BotState) -> Self {
-        let storage_folder =
-            env::var("STORAGE_FOLDER").expect("Expected STORAGE_FOLDER in the environment");
+/// Skill points with thousands separators, like 1,234,567.
+pub fn format_sp(sp: i64) -> String {
+    let digits = sp.abs().to_string();
+    let mut result = String::new();
+
+    for (index, digit) in digits.chars().enumerate() {
+        if index != 0 && (digits.len() - index).is_multiple_of(3) {
+            result.push(',');
+        }
+        result.push(digit);
+    }
+
+    if sp < 0 {
+        format!("-{}", result)
+    } else {
+        result
+    }
+}
 
+impl Monitor {
+    pub fn new(bot: BotState, config: MonitorConfig) -> Self {
         Self {
             bot,
-            storage_folder,
-            eve_character_list: Arc::new(Mutex::new(Vec::new())),
-            current_index: Arc::new(Mutex::new(0)),
+            _storage_lock: StorageLock::acquire(&config.storage.folder)
+                .unwrap_or_else(|error| panic!("Failed to lock storage: {}", error)),
+            storage: storage::open_storage(&config.storage)
+                .unwrap_or_else(|error| panic!("Failed to open storage: {}", error)),
+            discord_category_id: config.discord_category_id,
+            report_period: config.report_period,
+            queue_warnings: config.queue_warnings,
+            eve_character_list: Arc::new(Mutex::new(HashMap::new())),
+            schedule: Arc::new(Mutex::new(BinaryHeap::new())),
+            schedule_changed: Notify::new(),
+            workers: Arc::new(Semaphore::new(config.concurrency)),
+            index: std::sync::Mutex::new(HashMap::new()),
         }
     }
 
-    pub async fn start(bot: BotState) {
-        let monitor = Arc::new(Monitor::new(bot.clone()));
+    pub async fn start(bot: BotState, config: MonitorConfig) {
+        let monitor = Arc::new(Monitor::new(bot.clone(), config));
         bot.set_monitor(monitor.clone()).await;
         tokio::spawn(async move {
             monitor.load_all_eve_characters().await;
@@ -57,7 +157,27 @@ impl Monitor {
     pub async fn update_character(&self, character: &mut Character) -> bool {
         info!("[{}] Refreshing skills", character.id);
 
-        let mut storage = self.read_from_storage(character.id).unwrap();
+        let mut storage = match self.read_from_storage(character.id) {
+            Ok(storage) => storage,
+            /* Gone for good (deleted, or moved aside as corrupt); nothing left to refresh. */
+            Err(error) if !self.list_storage().contains(&character.id) => {
+                error!(
+                    "[{}] Storage is gone ({}); no longer monitoring character",
+                    character.id, error
+                );
+                return false;
+            }
+            /* Often temporary; try again later rather than dropping the character. */
+            Err(error) => {
+                character.retries += 1;
+                character.next_update = Utc::now() + schedule::RETRY_INTERVAL;
+                error!(
+                    "[{}] Failed to read storage (attempt {} / 8): {}",
+                    character.id, character.retries, error
+                );
+                return character.retries < 8;
+            }
+        };
 
         let tokens = self
             .bot
@@ -72,8 +192,17 @@ impl Monitor {
                 let skill_queue = self.bot.get_skill_queue(&access_token, character.id).await;
 
                 match (skills, skill_queue) {
-                    (Ok(mut skills), Ok(skill_queue)) => {
+                    (Ok(skills), Ok(skill_queue)) => {
                         character.retries = 0;
+                        character.next_update = schedule::next_update(
+                            &skill_queue.data,
+                            skills.expires.max(skill_queue.expires),
+                            &self.queue_warnings,
+                        );
+
+                        let modified = skills.modified || skill_queue.modified;
+                        let mut skills = skills.data;
+                        let skill_queue = skill_queue.data;
 
                         /* There can we skills in the queue that are in the past. Apply those to the actual skills already. */
                         for queue in &skill_queue.0 {
@@ -99,27 +228,99 @@ impl Monitor {
                             }
                         }
 
-                        let message = self.skill_queue_to_message(skill_queue).await;
-                        let _ = self
-                            .bot
-                            .discord_edit_last_message(storage.discord_channel_id, &message)
-                            .await;
-
-                        /* Don't check for changes if this is our first time loading. */
-                        if storage.skills.len() != 0 {
-                            let message = self.skills_change(&storage.skills, &skills.skills).await;
-                            if !message.is_empty() {
-                                let _ = self
-                                    .bot
-                                    .discord_send_message(
-                                        storage.discord_activity_thread_id,
-                                        &message,
-                                    )
-                                    .await;
+                        /* Even if ESI reports nothing changed, a skill in the queue could have finished since. */
+                        let unchanged = !modified && storage.skills == skills.skills;
+
+                        /* The status shows the progress of the skill in training; keep that current too.
+                         * It is only edited when the progress shown changed. */
+                        let training = pause::queue_state(&skill_queue.0, Utc::now())
+                            == pause::QueueState::Training;
+                        if !unchanged || training {
+                            let (message, embed) = self
+                                .status_message(&storage, &skill_queue.0, skills.total_sp)
+                                .await;
+                            self.update_status(&mut storage, &message, &embed).await;
+                        }
+
+                        if unchanged {
+                            info!("[{}] Skills unchanged", character.id);
+                        } else {
+                            /* Don't check for changes if this is our first time loading. */
+                            if !storage.skills.is_empty() {
+                                storage.history.record_finished(
+                                    Utc::now(),
+                                    &storage.skills,
+                                    &skills.skills,
+                                );
+
+                                let message =
+                                    self.skills_change(&storage.skills, &skills.skills).await;
+                                if !message.is_empty() {
+                                    let _ = self
+                                        .bot
+                                        .discord_send_message(
+                                            storage.discord_activity_thread_id,
+                                            &message,
+                                        )
+                                        .await;
+                                }
                             }
                         }
 
+                        /* Don't report the unallocated SP if this is our first time loading; characters
+                         * stored before the SP was kept have skills, but no total SP yet. */
+                        let unallocated_sp_change =
+                            if storage.skills.is_empty() || storage.total_sp == 0 {
+                                None
+                            } else {
+                                history::unallocated_sp_change(
+                                    storage.total_sp,
+                                    storage.unallocated_sp,
+                                    skills.total_sp,
+                                    skills.unallocated_sp,
+                                )
+                            };
+                        if let Some(change) = unallocated_sp_change {
+                            let _ = self
+                                .bot
+                                .discord_send_message(storage.discord_activity_thread_id, &change)
+                                .await;
+                        }
+
+                        let now = Utc::now();
+                        if let Some(change) = self.queue_state_change(&storage, &skill_queue.0, now)
+                        {
+                            let _ = self
+                                .bot
+                                .discord_send_message(storage.discord_activity_thread_id, &change)
+                                .await;
+                        }
+
                         storage.skills = skills.skills;
+                        storage.skill_queue = skill_queue.0;
+                        storage.total_sp = skills.total_sp;
+                        storage.unallocated_sp = skills.unallocated_sp;
+
+                        storage
+                            .history
+                            .record(now, skills.total_sp, skills.unallocated_sp);
+                        storage.history.record_training(now, &storage.skill_queue);
+                        if let Some(report) = self.sp_report(&mut storage, now).await {
+                            if self
+                                .bot
+                                .discord_send_message(storage.discord_activity_thread_id, &report)
+                                .await
+                                .is_ok()
+                            {
+                                storage.history.last_report = Some(now);
+                            }
+                        }
+                        if let Some(warning) = self.queue_warning(&mut storage, now) {
+                            let _ = self
+                                .bot
+                                .discord_send_message(storage.discord_activity_thread_id, &warning)
+                                .await;
+                        }
                     }
                     (Err(error), _) => {
                         character.retries += 1;
@@ -146,6 +347,10 @@ impl Monitor {
             }
         }
 
+        if character.retries != 0 {
+            character.next_update = Utc::now() + schedule::RETRY_INTERVAL;
+        }
+
         let res = if character.retries >= 8 {
             warn!(
                 "[{}] Character has failed to load 8 times in a row. Suspending character.",
@@ -159,168 +364,50 @@ impl Monitor {
 
             /* Mark the character as expired and inform our caller we should be removed. */
             storage.expired = true;
+
+            let (message, embed) = self
+                .status_message(&storage, &storage.skill_queue, storage.total_sp)
+                .await;
+            self.update_status(&mut storage, &message, &embed).await;
             false
         } else {
             true
         };
 
-        self.write_to_storage(character.id, storage.clone());
+        /* Hold the list while writing, so a character removed during the refresh isn't written back. */
+        let list = self.eve_character_list.lock().await;
+        if list.contains_key(&character.id) {
+            self.write_to_storage(character.id, storage.clone());
+        }
 
         res
     }
 
-    pub async fn run(&self) {
-        info!("Starting skill monitor thread");
-
-        loop {
-            let now = tokio::time::Instant::now();
-
-            let list_len = {
-                let mut list = self.eve_character_list.lock().await;
-                let mut index = self.current_index.lock().await;
-
-                if list.is_empty() {
-                    1
-                } else {
-                    let character = &mut list[*index];
-
-                    if !self.update_character(character).await {
-                        let character_id = character.id;
-                        list.retain(|c| c.id != character_id);
-
-                        /* Only wrap if we were the last entry; otherwise the current index is the next character. */
-                        if *index >= list.len() {
-                            *index = 0;
-                        }
-
-                        if list.is_empty() {
-                            1
-                        } else {
-                            list.len()
-                        }
-                    } else {
-                        *index = (*index + 1) % list.len();
-
-                        list.len()
-                    }
-                }
-            };
-
-            let sleep_time = tokio::time::Duration::from_secs(30 * 60 / list_len as u64);
-
-            /* Space updating characters evenly over a 30 minute period. */
-            let elapsed = now.elapsed();
-            if elapsed < sleep_time {
-                tokio::time::sleep(sleep_time - elapsed).await;
+    /// Look up skill names in a single request; unknown skills are left out.
+    async fn lookup_skill_names(&self, skill_ids: &[i32]) -> HashMap<i32, String> {
+        match self.bot.lookup_skill_names(skill_ids).await {
+            Ok(skill_names) => skill_names,
+            Err(error) => {
+                warn!("Failed to lookup skill names: {}", error);
+                HashMap::new()
             }
         }
     }
 
-    async fn skill_queue_to_message(&self, skill_queue: crate::esi::EsiSkillQueue) -> String {
-        let mut message = String::new();
-        let mut index = 0;
-        for queue in &skill_queue.0 {
-            /* Only list entries that are stalled or are in the future. */
-            if queue.finish_date.is_some() && queue.finish_date <= Some(chrono::Utc::now()) {
-                continue;
-            }
+    async fn skills_change(&self, old_skills: &[EsiSkill], new_skills: &[EsiSkill]) -> String {
+        let changes = diff::skills_diff(old_skills, new_skills);
 
-            let finish_date = match queue.finish_date {
-                Some(finish_date) => format!("finish training <t:{}:R>", finish_date.timestamp()),
-                None => "never finish training".to_string(),
-            };
-            let skill_name = self.bot.lookup_skill_name(queue.skill_id).await;
-            let skill_name = match skill_name {
-                Ok(skill_name) => skill_name,
-                Err(error) => {
-                    warn!(
-                        "Failed to lookup skill name for skill ID {}: {}",
-                        queue.skill_id, error
-                    );
-                    "Unknown".to_string()
-                }
-            };
+        let skill_ids: Vec<i32> = changes.iter().map(|change| change.skill_id()).collect();
+        let skill_names = self.lookup_skill_names(&skill_ids).await;
 
-            message += &format!(
-                "- `{} {}` will {}.\n",
-                skill_name,
-                level_to_roman(queue.finished_level),
-                finish_date,
-            );
-            index += 1;
-            if index >= 5 {
-                message += &format!("... and {} more.\n", skill_queue.0.len() - 5);
-                break;
-            }
-        }
-
-        /* Find the last skill in queue. */
-        let finish_date = match skill_queue.0.last() {
-            Some(queue) => match queue.finish_date {
-                Some(finish_date) => format!("finish <t:{}:R>", finish_date.timestamp()),
-                None => "never finish".to_string(),
-            },
-            None => "never finish".to_string(),
-        };
-
-        message += &format!("\nSkill queue will {}.\n", finish_date);
-        message += &format!(
-            "\nNext update expected <t:{}:R>.\n",
-            chrono::Utc::now().timestamp() + 30 * 60
-        );
-
-        message
-    }
-
-    async fn skills_change(
-        &self,
-        old_skills: &Vec<EsiSkill>,
-        new_skills: &Vec<EsiSkill>,
-    ) -> String {
         let mut message = String::new();
 
-        for new_skill in new_skills {
-            let old_skill = old_skills
-                .iter()
-                .find(|&s| s.skill_id == new_skill.skill_id);
-
-            match old_skill {
-                Some(old_skill) => {
-                    if old_skill.trained_skill_level != new_skill.trained_skill_level {
-                        let new_skill_name = self
-                            .bot
-                            .lookup_skill_name(new_skill.skill_id)
-                            .await
-                            .unwrap_or("Unknown".to_string());
-
-                        message += &format!(
-                            "`{} {}` has finished training.\n",
-                            new_skill_name,
-                            level_to_roman(new_skill.trained_skill_level),
-                        );
-                    }
-                }
-                None => {
-                    let new_skill_name = self
-                        .bot
-                        .lookup_skill_name(new_skill.skill_id)
-                        .await
-                        .unwrap_or("Unknown".to_string());
-
-                    match new_skill.trained_skill_level {
-                        0 => {
-                            message += &format!("`{}` has been injected.\n", new_skill_name,);
-                        }
-                        _ => {
-                            message += &format!(
-                                "`{} {}` has finished training.\n",
-                                new_skill_name,
-                                level_to_roman(new_skill.trained_skill_level),
-                            );
-                        }
-                    }
-                }
-            }
+        for change in changes {
+            let skill_name = skill_names
+                .get(&change.skill_id())
+                .map_or("Unknown", |name| name.as_str());
+
+            message += &change.describe(skill_name);
         }
 
         message
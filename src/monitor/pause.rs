@@ -0,0 +1,48 @@
+use chrono::{DateTime, Utc};
+
+use crate::esi::EsiSkillQueueItem;
+
+use super::{storage::StorageV2, Monitor};
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum QueueState {
+    Empty,
+    Training,
+    /* Skills in the queue, but none training; paused by hand, or an Alpha clone. */
+    Paused,
+}
+
+pub fn queue_state(skill_queue: &[EsiSkillQueueItem], now: DateTime<Utc>) -> QueueState {
+    /* Entries that finished already are left in the queue till ESI catches up. */
+    let head = skill_queue
+        .iter()
+        .find(|q| q.finish_date.is_none_or(|finish_date| finish_date > now));
+
+    match head {
+        None => QueueState::Empty,
+        Some(head) if head.finish_date.is_none() => QueueState::Paused,
+        Some(_) => QueueState::Training,
+    }
+}
+
+impl Monitor {
+    /// A message if the queue went from training to paused, or back.
+    pub(super) fn queue_state_change(
+        &self,
+        storage: &StorageV2,
+        new_skill_queue: &[EsiSkillQueueItem],
+        now: DateTime<Utc>,
+    ) -> Option<String> {
+        let old_state = queue_state(&storage.skill_queue, now);
+        let new_state = queue_state(new_skill_queue, now);
+
+        match (old_state, new_state) {
+            (QueueState::Training, QueueState::Paused) => Some(format!(
+                "<@{}>: Skill queue paused.",
+                storage.discord_character_id
+            )),
+            (QueueState::Paused, QueueState::Training) => Some("Skill queue resumed.".to_string()),
+            _ => None,
+        }
+    }
+}
@@ -0,0 +1,39 @@
+use chrono::{DateTime, Utc};
+
+use crate::esi::EsiSkillQueueItem;
+
+pub struct Progress {
+    /* Of the level currently training, from 0 to 100. */
+    pub percent: f64,
+    pub sp_per_hour: f64,
+}
+
+/// How far along the skill currently training is; None if nothing is training.
+pub fn head_progress(skill_queue: &[EsiSkillQueueItem], now: DateTime<Utc>) -> Option<Progress> {
+    let head = skill_queue
+        .iter()
+        .find(|q| q.finish_date.is_none_or(|finish_date| finish_date > now))?;
+    let start_date = head.start_date?;
+    let finish_date = head.finish_date?;
+
+    /* ESI only updates the SP at the start; training goes at a constant rate till the finish. */
+    let hours = (finish_date - start_date).num_seconds() as f64 / 3600.0;
+    if hours <= 0.0 {
+        return None;
+    }
+    let sp_per_hour = (head.level_end_sp - head.training_start_sp) as f64 / hours;
+
+    let elapsed = (now - start_date).num_seconds().max(0) as f64 / 3600.0;
+    let current_sp = head.training_start_sp as f64 + sp_per_hour * elapsed;
+    let level_sp = (head.level_end_sp - head.level_start_sp) as f64;
+    let percent = if level_sp > 0.0 {
+        ((current_sp - head.level_start_sp as f64) / level_sp * 100.0).clamp(0.0, 100.0)
+    } else {
+        100.0
+    };
+
+    Some(Progress {
+        percent,
+        sp_per_hour,
+    })
+}
@@ -0,0 +1,162 @@
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+use std::sync::Arc;
+
+use chrono::{DateTime, TimeDelta, Utc};
+use tokio::sync::OwnedSemaphorePermit;
+use tracing::info;
+
+use crate::esi::EsiSkillQueue;
+
+use super::{warnings::queue_end, Character, Monitor};
+
+/* ESI takes a moment to reflect a finished skill; don't poll on the exact second. */
+const FINISH_GRACE: TimeDelta = TimeDelta::seconds(60);
+/* Even without anything finishing, the user might have changed the queue. */
+const MAX_INTERVAL_TRAINING: TimeDelta = TimeDelta::minutes(30);
+const MAX_INTERVAL_IDLE: TimeDelta = TimeDelta::hours(2);
+pub const RETRY_INTERVAL: TimeDelta = TimeDelta::minutes(30);
+
+/// Characters ordered by when they are due for a refresh; the earliest is on top.
+pub type Schedule = BinaryHeap<Reverse<(DateTime<Utc>, u64)>>;
+
+/// When a character should be refreshed next, based on its skill queue, the queue warnings, and
+/// the ESI cache expiry.
+pub fn next_update(
+    skill_queue: &EsiSkillQueue,
+    expires: Option<DateTime<Utc>>,
+    queue_warnings: &[TimeDelta],
+) -> DateTime<Utc> {
+    let now = Utc::now();
+
+    let next_finish = skill_queue
+        .0
+        .iter()
+        .filter_map(|q| q.finish_date)
+        .find(|finish_date| *finish_date > now);
+
+    let next_update = match next_finish {
+        Some(finish_date) => (finish_date + FINISH_GRACE).min(now + MAX_INTERVAL_TRAINING),
+        None => now + MAX_INTERVAL_IDLE,
+    };
+
+    /* Be on time for the next queue warning. */
+    let next_warning = queue_end(&skill_queue.0).and_then(|queue_end| {
+        queue_warnings
+            .iter()
+            .map(|threshold| queue_end - *threshold)
+            .filter(|warning| *warning > now)
+            .min()
+    });
+    let next_update = match next_warning {
+        Some(warning) => next_update.min(warning),
+        None => next_update,
+    };
+
+    /* Polling before ESI refreshes its cache only returns the same data. */
+    match expires {
+        Some(expires) => next_update.max(expires),
+        None => next_update,
+    }
+}
+
+impl Monitor {
+    /// Add a character to the schedule; a character already in the schedule is rescheduled.
+    pub async fn schedule_eve_character(&self, character: Character) {
+        let mut schedule = self.schedule.lock().await;
+        let mut list = self.eve_character_list.lock().await;
+
+        schedule.push(Reverse((character.next_update, character.id)));
+        list.insert(character.id, character);
+
+        self.schedule_changed.notify_one();
+    }
+
+    pub async fn run(self: Arc<Self>) {
+        info!("Starting skill monitor thread");
+
+        loop {
+            /* Only pick the next character once there is a worker available to refresh it. */
+            let worker = self.workers.clone().acquire_owned().await.unwrap();
+
+            let next = {
+                let schedule = self.schedule.lock().await;
+                schedule.peek().map(|Reverse(entry)| *entry)
+            };
+
+            let Some((next_update, eve_character_id)) = next else {
+                self.schedule_changed.notified().await;
+                continue;
+            };
+
+            /* Wait till the next character is due, unless the schedule changes in the meantime. */
+            let now = Utc::now();
+            if next_update > now {
+                let sleep_time = (next_update - now).to_std().unwrap_or_default();
+                tokio::select! {
+                    _ = tokio::time::sleep(sleep_time) => {}
+                    _ = self.schedule_changed.notified() => {}
+                }
+                continue;
+            }
+
+            self.schedule.lock().await.pop();
+
+            let character = {
+                let list = self.eve_character_list.lock().await;
+
+                /* Entries of removed or rescheduled characters are left in the schedule; skip those. */
+                match list.get(&eve_character_id) {
+                    Some(character) if character.next_update == next_update => character.clone(),
+                    _ => continue,
+                }
+            };
+
+            /* Refresh without holding any lock; installs and removals shouldn't wait on ESI. */
+            let monitor = self.clone();
+            tokio::spawn(async move {
+                monitor.refresh(character, worker).await;
+            });
+        }
+    }
+
+    async fn refresh(&self, mut character: Character, worker: OwnedSemaphorePermit) -> bool {
+        let keep = self.update_character(&mut character).await;
+        drop(worker);
+
+        let mut schedule = self.schedule.lock().await;
+        let mut list = self.eve_character_list.lock().await;
+
+        /* The character might have been removed while we were refreshing it. */
+        if !list.contains_key(&character.id) {
+            return false;
+        }
+
+        if keep {
+            schedule.push(Reverse((character.next_update, character.id)));
+            list.insert(character.id, character);
+        } else {
+            list.remove(&character.id);
+        }
+
+        self.schedule_changed.notify_one();
+        keep
+    }
+
+    /// Refresh a character right away, regardless of when it is due.
+    ///
+    /// Returns whether the character is still monitored afterwards.
+    pub async fn refresh_now(&self, eve_character_id: u64) -> bool {
+        let worker = self.workers.clone().acquire_owned().await.unwrap();
+
+        let character = {
+            let list = self.eve_character_list.lock().await;
+            match list.get(&eve_character_id) {
+                Some(character) => character.clone(),
+                None => return false,
+            }
+        };
+
+        self.refresh(character, worker).await
+    }
+}
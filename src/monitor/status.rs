@@ -0,0 +1,256 @@
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+
+use chrono::Utc;
+use tracing::{info, warn};
+
+use crate::esi::EsiSkillQueueItem;
+use crate::notifier::{Embed, EmbedField};
+
+use super::pause::{queue_state, QueueState};
+use super::{format_sp, level_to_roman, progress, storage::StorageV2, warnings, Monitor};
+
+/* Colour of the embed, by health of the skill queue. */
+const COLOUR_TRAINING: u32 = 0x2ecc71;
+const COLOUR_PAUSED: u32 = 0xf1c40f;
+const COLOUR_EMPTY: u32 = 0xe74c3c;
+const COLOUR_EXPIRED: u32 = 0x95a5a6;
+
+/* Discord allows 25 fields per embed; more than this is hard to read anyway. */
+const MAX_FIELDS: usize = 10;
+
+impl Monitor {
+    /// The status of a character: an embed, and a line of text for clients that can't show embeds.
+    pub(super) async fn status_message(
+        &self,
+        storage: &StorageV2,
+        skill_queue: &[EsiSkillQueueItem],
+        total_sp: i64,
+    ) -> (String, Embed) {
+        let now = Utc::now();
+
+        /* Only list entries that are stalled or are in the future. */
+        let pending: Vec<&EsiSkillQueueItem> = skill_queue
+            .iter()
+            .filter(|q| q.finish_date.is_none_or(|finish_date| finish_date > now))
+            .collect();
+
+        let skill_ids: Vec<i32> = pending.iter().map(|q| q.skill_id).collect();
+        let skill_names = self.lookup_skill_names(&skill_ids).await;
+        let skill_name = |queue: &EsiSkillQueueItem| {
+            format!(
+                "{} {}",
+                skill_names
+                    .get(&queue.skill_id)
+                    .map_or("Unknown", |name| name.as_str()),
+                level_to_roman(queue.finished_level),
+            )
+        };
+
+        let mut fields = Vec::new();
+        for (index, queue) in pending.iter().take(MAX_FIELDS).enumerate() {
+            let mut value = match queue.finish_date {
+                Some(finish_date) => format!("Finishes <t:{}:R>.", finish_date.timestamp()),
+                None => "Never finishes.".to_string(),
+            };
+            if index == 0 {
+                if let Some(progress) = progress::head_progress(skill_queue, now) {
+                    value += &format!(
+                        "\n{:.0}% done, training at {} SP/hour.",
+                        progress.percent.floor(),
+                        format_sp(progress.sp_per_hour.round() as i64),
+                    );
+                }
+            }
+
+            fields.push(EmbedField {
+                name: skill_name(queue),
+                value,
+            });
+        }
+
+        let state = queue_state(skill_queue, now);
+        let (text, colour, mut description) = if storage.expired {
+            (
+                "Monitoring suspended.".to_string(),
+                COLOUR_EXPIRED,
+                "Monitoring suspended. Please re-authenticate with /monitor to continue monitoring.\n"
+                    .to_string(),
+            )
+        } else {
+            match (state, warnings::queue_end(skill_queue)) {
+                (QueueState::Training, Some(queue_end)) => (
+                    format!(
+                        "`{}` is training; skill queue will finish <t:{}:R>.",
+                        skill_name(pending[0]),
+                        queue_end.timestamp()
+                    ),
+                    COLOUR_TRAINING,
+                    /* Discord keeps the time left current, without the status being edited. */
+                    format!(
+                        "Skill queue will finish <t:{}:R>, on <t:{}:f>.\n",
+                        queue_end.timestamp(),
+                        queue_end.timestamp(),
+                    ),
+                ),
+                (QueueState::Training, None) => (
+                    format!("`{}` is training.", skill_name(pending[0])),
+                    COLOUR_TRAINING,
+                    "Skill queue will never finish.\n".to_string(),
+                ),
+                (QueueState::Paused, _) => (
+                    "Skill queue paused.".to_string(),
+                    COLOUR_PAUSED,
+                    "Skill queue is paused.\n".to_string(),
+                ),
+                (QueueState::Empty, _) => (
+                    "Skill queue empty.".to_string(),
+                    COLOUR_EMPTY,
+                    "Skill queue is empty.\n".to_string(),
+                ),
+            }
+        };
+
+        if pending.len() > MAX_FIELDS {
+            description += &format!("... and {} more.\n", pending.len() - MAX_FIELDS);
+        }
+
+        let embed = Embed {
+            title: storage.eve_character_name.clone(),
+            description,
+            thumbnail: Some(format!(
+                "https://images.evetech.net/characters/{}/portrait",
+                storage.eve_character_id
+            )),
+            colour,
+            fields,
+            footer: format!("Total SP: {}", format_sp(total_sp)),
+            timestamp: now,
+        };
+
+        (text, embed)
+    }
+
+    /// Edit the status message; if it was deleted (or never tracked), post and pin a new one.
+    ///
+    /// Nothing is sent if the status is the same as last posted.
+    pub(super) async fn update_status(
+        &self,
+        storage: &mut StorageV2,
+        message: &str,
+        embed: &Embed,
+    ) {
+        let eve_character_id = storage.eve_character_id;
+        let discord_channel_id = storage.discord_channel_id;
+
+        /* Before the status message was tracked, it was the last message in the channel. Adopt it,
+         * rather than leaving it behind. */
+        if storage.discord_status_message_id.is_none() {
+            match self.bot.discord_last_own_message(discord_channel_id).await {
+                Ok(Some(message_id)) => {
+                    info!(
+                        "[{}] Adopting the last message as status message",
+                        eve_character_id
+                    );
+                    storage.discord_status_message_id = Some(message_id);
+                    if let Err(error) = self
+                        .bot
+                        .discord_pin_message(discord_channel_id, message_id)
+                        .await
+                    {
+                        warn!(
+                            "[{}] Failed to pin status message: {}",
+                            eve_character_id, error
+                        );
+                    }
+                }
+                Ok(None) => {}
+                Err(error) => warn!(
+                    "[{}] Failed to find the previous status message: {}",
+                    eve_character_id, error
+                ),
+            }
+        }
+
+        let hash = status_hash(message, embed);
+        if let Some(message_id) = storage.discord_status_message_id {
+            if storage.discord_status_hash == Some(hash) {
+                return;
+            }
+
+            match self
+                .bot
+                .discord_edit_message(discord_channel_id, message_id, message, embed)
+                .await
+            {
+                Ok(true) => {
+                    storage.discord_status_hash = Some(hash);
+                    return;
+                }
+                Ok(false) => info!(
+                    "[{}] Status message was deleted; posting a new one",
+                    eve_character_id
+                ),
+                Err(error) => {
+                    warn!(
+                        "[{}] Failed to edit status message: {}",
+                        eve_character_id, error
+                    );
+                    return;
+                }
+            }
+        }
+
+        let message_id = match self
+            .bot
+            .discord_send_message(discord_channel_id, message)
+            .await
+        {
+            Ok(message_id) => message_id,
+            Err(error) => {
+                warn!(
+                    "[{}] Failed to post status message: {}",
+                    eve_character_id, error
+                );
+                return;
+            }
+        };
+        storage.discord_status_message_id = Some(message_id);
+
+        /* Messages are posted as text; the embed is added by editing. */
+        match self
+            .bot
+            .discord_edit_message(discord_channel_id, message_id, message, embed)
+            .await
+        {
+            Ok(_) => storage.discord_status_hash = Some(hash),
+            Err(error) => warn!(
+                "[{}] Failed to add embed to status message: {}",
+                eve_character_id, error
+            ),
+        }
+        if let Err(error) = self
+            .bot
+            .discord_pin_message(discord_channel_id, message_id)
+            .await
+        {
+            warn!(
+                "[{}] Failed to pin status message: {}",
+                eve_character_id, error
+            );
+        }
+    }
+}
+
+/* The timestamp is left out; it only tells when the status was rendered. */
+fn status_hash(message: &str, embed: &Embed) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    message.hash(&mut hasher);
+    embed.title.hash(&mut hasher);
+    embed.description.hash(&mut hasher);
+    embed.thumbnail.hash(&mut hasher);
+    embed.colour.hash(&mut hasher);
+    embed.fields.hash(&mut hasher);
+    embed.footer.hash(&mut hasher);
+    hasher.finish()
+}
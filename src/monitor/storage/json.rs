@@ -0,0 +1,195 @@
+use std::io::Write;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use tracing::error;
+
+use super::{decode, parse, serialize, StorageBackend, StorageKey, StorageV2};
+
+/* Makes temporary file names unique, so writes to the same character can't mix. */
+static TEMP_COUNTER: AtomicU64 = AtomicU64::new(0);
+
+/// Write to a temporary file first and rename it over the old one; a crash halfway leaves either
+/// the old or the new version, never a truncated file.
+pub fn write_atomic(path: &str, data: &str) -> Result<(), String> {
+    let temp_path = format!(
+        "{}.{}-{}.tmp",
+        path,
+        std::process::id(),
+        TEMP_COUNTER.fetch_add(1, Ordering::Relaxed)
+    );
+
+    let result = replace(path, &temp_path, data);
+    if result.is_err() {
+        let _ = std::fs::remove_file(&temp_path);
+    }
+    result
+}
+
+fn replace(path: &str, temp_path: &str, data: &str) -> Result<(), String> {
+    let mut file = std::fs::File::create(temp_path).map_err(|e| e.to_string())?;
+    file.write_all(data.as_bytes()).map_err(|e| e.to_string())?;
+    file.sync_all().map_err(|e| e.to_string())?;
+    drop(file);
+
+    std::fs::rename(temp_path, path).map_err(|e| e.to_string())?;
+
+    /* Make sure the rename itself is on disk too. */
+    if let Some(folder) = std::path::Path::new(path).parent() {
+        if let Ok(folder) = std::fs::File::open(folder) {
+            let _ = folder.sync_all();
+        }
+    }
+
+    Ok(())
+}
+
+/// Files in `folder` that still hold a refresh token in plain text, backups included.
+pub fn plain_text_files(folder: &str) -> Result<Vec<String>, String> {
+    let mut files = Vec::new();
+
+    for file in std::fs::read_dir(folder).map_err(|e| e.to_string())? {
+        let path = file.map_err(|e| e.to_string())?.path();
+        let Some(name) = path.file_name().and_then(|name| name.to_str()) else {
+            continue;
+        };
+        if !name.starts_with("char-") || !(name.ends_with(".json") || name.ends_with(".json.bak")) {
+            continue;
+        }
+
+        /* Unreadable files are left to whoever reads them; only the version matters here. */
+        let Ok(data) = std::fs::read_to_string(&path) else {
+            continue;
+        };
+        if parse(&data).is_ok_and(|storage| !storage.is_current()) {
+            files.push(path.to_string_lossy().to_string());
+        }
+    }
+
+    files.sort();
+    Ok(files)
+}
+
+/// Every character in its own `char-<id>.json` in the storage folder.
+pub struct JsonStorage {
+    folder: String,
+    key: StorageKey,
+}
+
+impl JsonStorage {
+    pub fn new(folder: &str, key: StorageKey) -> Self {
+        Self {
+            folder: folder.to_string(),
+            key,
+        }
+    }
+
+    fn path(&self, eve_character_id: u64) -> String {
+        format!("{}/char-{}.json", self.folder, eve_character_id)
+    }
+
+    /* Move an unreadable file out of the way, so it is kept for inspection but no longer loaded. */
+    fn quarantine(&self, eve_character_id: u64, reason: &str) {
+        let path = self.path(eve_character_id);
+        let corrupt_path = format!("{}.corrupt", path);
+
+        error!(
+            "[{}] Storage is unreadable ({}); moving it to {}",
+            eve_character_id, reason, corrupt_path
+        );
+        if let Err(error) = std::fs::rename(&path, &corrupt_path) {
+            error!("[{}] Failed to move {}: {}", eve_character_id, path, error);
+        }
+    }
+
+    /* Without an index, finding characters by anything but their ID means reading them all. */
+    fn list_where(&self, filter: impl Fn(&StorageV2) -> bool) -> Result<Vec<u64>, String> {
+        Ok(self
+            .list()?
+            .into_iter()
+            .filter(|eve_character_id| {
+                self.read(*eve_character_id)
+                    .is_ok_and(|storage| filter(&storage))
+            })
+            .collect())
+    }
+}
+
+impl StorageBackend for JsonStorage {
+    fn read(&self, eve_character_id: u64) -> Result<StorageV2, String> {
+        let storage = match std::fs::read_to_string(self.path(eve_character_id)) {
+            Ok(storage) => storage,
+            Err(error) if error.kind() == std::io::ErrorKind::NotFound => {
+                return Err("Character not found.".to_string())
+            }
+            /* Possibly temporary (permissions, too many open files); leave the file be. */
+            Err(error) => return Err(error.to_string()),
+        };
+
+        let storage =
+            parse(&storage).inspect_err(|error| self.quarantine(eve_character_id, error))?;
+        let (storage, upgraded) = decode(storage, &self.key)?;
+
+        /* Don't leave the old format (and with it, a plain text token) on disk. */
+        if upgraded {
+            self.write(&storage)?;
+            self.remove_backup(eve_character_id)?;
+        }
+
+        Ok(storage)
+    }
+
+    fn write(&self, storage: &StorageV2) -> Result<(), String> {
+        let path = self.path(storage.eve_character_id);
+
+        /* Keep the previous version around, in case the new one turns out to be bad. */
+        if std::path::Path::new(&path).exists() {
+            std::fs::copy(&path, format!("{}.bak", path)).map_err(|e| e.to_string())?;
+        }
+
+        write_atomic(&path, &serialize(storage, &self.key))
+    }
+
+    fn delete(&self, eve_character_id: u64) -> Result<(), String> {
+        let path = self.path(eve_character_id);
+
+        std::fs::remove_file(&path).map_err(|e| e.to_string())?;
+        self.remove_backup(eve_character_id)
+    }
+
+    fn list(&self) -> Result<Vec<u64>, String> {
+        let mut eve_character_ids = Vec::new();
+
+        let storage_files = std::fs::read_dir(&self.folder).map_err(|e| e.to_string())?;
+        for storage_file in storage_files {
+            let storage_file = storage_file.map_err(|e| e.to_string())?;
+
+            /* Characters are stored in char-<number>.json. */
+            if let Some(storage_file) = storage_file.file_name().to_str() {
+                if let Some(eve_character_id) = storage_file
+                    .strip_prefix("char-")
+                    .and_then(|s| s.strip_suffix(".json"))
+                    .and_then(|s| s.parse().ok())
+                {
+                    eve_character_ids.push(eve_character_id);
+                }
+            }
+        }
+
+        Ok(eve_character_ids)
+    }
+
+    fn list_by_discord_user(&self, discord_character_id: u64) -> Result<Vec<u64>, String> {
+        self.list_where(|storage| storage.discord_character_id == discord_character_id)
+    }
+
+    fn list_by_discord_guild(&self, discord_guild_id: u64) -> Result<Vec<u64>, String> {
+        self.list_where(|storage| storage.discord_guild_id == discord_guild_id)
+    }
+
+    fn remove_backup(&self, eve_character_id: u64) -> Result<(), String> {
+        match std::fs::remove_file(format!("{}.bak", self.path(eve_character_id))) {
+            Err(error) if error.kind() != std::io::ErrorKind::NotFound => Err(error.to_string()),
+            _ => Ok(()),
+        }
+    }
+}
@@ -0,0 +1,86 @@
+use std::env;
+
+use openssl::symm::{decrypt_aead, encrypt_aead, Cipher};
+
+const KEY_LENGTH: usize = 32;
+const NONCE_LENGTH: usize = 12;
+const TAG_LENGTH: usize = 16;
+
+/// AES-256-GCM key used to encrypt refresh tokens at rest.
+#[derive(Clone)]
+pub struct StorageKey([u8; KEY_LENGTH]);
+
+impl StorageKey {
+    /// Parse a key as produced by `generate`: 32 random bytes, base64 encoded.
+    pub fn parse(key: &str) -> Result<Self, String> {
+        let key = openssl::base64::decode_block(key.trim()).map_err(|e| e.to_string())?;
+
+        Ok(StorageKey(key.try_into().map_err(|_| {
+            format!("Storage key should be {} bytes", KEY_LENGTH)
+        })?))
+    }
+
+    pub fn from_file(path: &str) -> Result<Self, String> {
+        let key = std::fs::read_to_string(path).map_err(|e| format!("{}: {}", path, e))?;
+        Self::parse(&key).map_err(|e| format!("{}: {}", path, e))
+    }
+
+    /// The key from STORAGE_KEY, or else from the file STORAGE_KEY_FILE points to.
+    pub fn from_env() -> Result<Self, String> {
+        if let Ok(key) = env::var("STORAGE_KEY") {
+            return Self::parse(&key);
+        }
+        match env::var("STORAGE_KEY_FILE") {
+            Ok(path) => Self::from_file(&path),
+            Err(_) => {
+                Err("Expected STORAGE_KEY or STORAGE_KEY_FILE in the environment".to_string())
+            }
+        }
+    }
+
+    pub fn generate() -> String {
+        let mut key = [0; KEY_LENGTH];
+        openssl::rand::rand_bytes(&mut key).unwrap();
+        openssl::base64::encode_block(&key)
+    }
+
+    /* The result is the nonce, ciphertext and tag, base64 encoded as a single string. */
+    pub fn encrypt(&self, plaintext: &str) -> String {
+        let mut nonce = [0; NONCE_LENGTH];
+        openssl::rand::rand_bytes(&mut nonce).unwrap();
+
+        let mut tag = [0; TAG_LENGTH];
+        let ciphertext = encrypt_aead(
+            Cipher::aes_256_gcm(),
+            &self.0,
+            Some(&nonce),
+            &[],
+            plaintext.as_bytes(),
+            &mut tag,
+        )
+        .unwrap();
+
+        openssl::base64::encode_block(&[&nonce[..], &ciphertext, &tag].concat())
+    }
+
+    pub fn decrypt(&self, encrypted: &str) -> Result<String, String> {
+        let data = openssl::base64::decode_block(encrypted).map_err(|e| e.to_string())?;
+        if data.len() < NONCE_LENGTH + TAG_LENGTH {
+            return Err("Encrypted token is too short".to_string());
+        }
+
+        let (nonce, rest) = data.split_at(NONCE_LENGTH);
+        let (ciphertext, tag) = rest.split_at(rest.len() - TAG_LENGTH);
+        let plaintext = decrypt_aead(
+            Cipher::aes_256_gcm(),
+            &self.0,
+            Some(nonce),
+            &[],
+            ciphertext,
+            tag,
+        )
+        .map_err(|_| "Failed to decrypt token; wrong storage key?".to_string())?;
+
+        String::from_utf8(plaintext).map_err(|e| e.to_string())
+    }
+}
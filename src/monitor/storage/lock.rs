@@ -0,0 +1,24 @@
+use std::fs::{File, TryLockError};
+
+/// Exclusive use of the storage folder, held by the bot while it runs.
+///
+/// The operating system releases it when the process ends, also after a crash.
+pub struct StorageLock {
+    _file: File,
+}
+
+impl StorageLock {
+    pub fn acquire(folder: &str) -> Result<Self, String> {
+        let path = format!("{}/storage.lock", folder);
+        let file = File::create(&path).map_err(|e| format!("{}: {}", path, e))?;
+
+        match file.try_lock() {
+            Ok(()) => Ok(StorageLock { _file: file }),
+            Err(TryLockError::WouldBlock) => Err(format!(
+                "{} is in use by another process; is the bot still running?",
+                folder
+            )),
+            Err(TryLockError::Error(error)) => Err(format!("{}: {}", path, error)),
+        }
+    }
+}
@@ -0,0 +1,374 @@
+use std::env;
+
+use serde::{Deserialize, Serialize};
+use tracing::{error, info, warn};
+
+use crate::esi::{EsiSkill, EsiSkillQueueItem};
+
+use super::{history::SpHistory, Monitor};
+
+mod json;
+mod key;
+mod lock;
+mod sqlite;
+
+pub(crate) use json::write_atomic;
+pub use json::JsonStorage;
+pub use key::StorageKey;
+pub use lock::StorageLock;
+pub use sqlite::SqliteStorage;
+
+/// A character as stored before tokens were encrypted; only read to migrate it.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct StorageV1 {
+    pub refresh_token: String,
+    pub expired: bool,
+    pub eve_character_id: u64,
+    pub eve_character_name: String,
+    #[serde(default)]
+    pub scopes: Vec<String>,
+    pub discord_character_id: u64,
+    pub discord_guild_id: u64,
+    pub discord_channel_id: u64,
+    pub discord_activity_thread_id: u64,
+    pub skills: Vec<EsiSkill>,
+    #[serde(default)]
+    pub skill_queue: Vec<EsiSkillQueueItem>,
+    #[serde(default)]
+    pub total_sp: i64,
+    #[serde(default)]
+    pub unallocated_sp: i32,
+}
+
+/// A character as currently stored. On disk, the refresh token is encrypted.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct StorageV2 {
+    pub refresh_token: String,
+    pub expired: bool,
+    pub eve_character_id: u64,
+    pub eve_character_name: String,
+    pub scopes: Vec<String>,
+    pub discord_character_id: u64,
+    pub discord_guild_id: u64,
+    pub discord_channel_id: u64,
+    pub discord_activity_thread_id: u64,
+    /* The pinned message showing the skill queue; None for characters stored before it was tracked. */
+    #[serde(default)]
+    pub discord_status_message_id: Option<u64>,
+    /* Hash of the status as last posted, to skip edits that wouldn't change anything. */
+    #[serde(default)]
+    pub discord_status_hash: Option<u64>,
+    pub skills: Vec<EsiSkill>,
+    pub skill_queue: Vec<EsiSkillQueueItem>,
+    pub total_sp: i64,
+    pub unallocated_sp: i32,
+    #[serde(default)]
+    pub history: SpHistory,
+    /* Queue warning thresholds (in minutes) already posted for the current queue. */
+    #[serde(default)]
+    pub queue_warnings: Vec<i64>,
+}
+
+impl From<StorageV1> for StorageV2 {
+    fn from(storage: StorageV1) -> Self {
+        Self {
+            refresh_token: storage.refresh_token,
+            expired: storage.expired,
+            eve_character_id: storage.eve_character_id,
+            eve_character_name: storage.eve_character_name,
+            scopes: storage.scopes,
+            discord_character_id: storage.discord_character_id,
+            discord_guild_id: storage.discord_guild_id,
+            discord_channel_id: storage.discord_channel_id,
+            discord_activity_thread_id: storage.discord_activity_thread_id,
+            discord_status_message_id: None,
+            discord_status_hash: None,
+            skills: storage.skills,
+            skill_queue: storage.skill_queue,
+            total_sp: storage.total_sp,
+            unallocated_sp: storage.unallocated_sp,
+            history: SpHistory::default(),
+            queue_warnings: Vec::new(),
+        }
+    }
+}
+
+/* To change the format: add a StorageVn+1, a From<StorageVn> for it, a variant below, and a step
+ * in Storage::migrate. Old versions are upgraded on read, and written back in the new version.
+ * A new field with a sensible default (#[serde(default)]) doesn't need a new version. */
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(tag = "version")]
+enum Storage {
+    #[serde(rename = "1")]
+    V1(StorageV1),
+    #[serde(rename = "2")]
+    V2(StorageV2),
+}
+
+impl Storage {
+    fn is_current(&self) -> bool {
+        matches!(self, Storage::V2(_))
+    }
+
+    /* Since version 2, the refresh token is encrypted on disk. */
+    fn decrypt(self, key: &StorageKey) -> Result<Self, String> {
+        match self {
+            Storage::V1(storage) => Ok(Storage::V1(storage)),
+            Storage::V2(storage) => Ok(Storage::V2(StorageV2 {
+                refresh_token: key.decrypt(&storage.refresh_token)?,
+                ..storage
+            })),
+        }
+    }
+
+    /// Upgrade one version at a time, till the current version.
+    fn migrate(self) -> StorageV2 {
+        match self {
+            Storage::V1(storage) => Storage::V2(storage.into()).migrate(),
+            Storage::V2(storage) => storage,
+        }
+    }
+}
+
+/// Where and how characters are persisted.
+///
+/// Every record is stored in the versioned `Storage` format, whatever the backend.
+pub trait StorageBackend: Send + Sync {
+    fn read(&self, eve_character_id: u64) -> Result<StorageV2, String>;
+    /// Replace (or create) a character; either the whole record is written, or nothing is.
+    fn write(&self, storage: &StorageV2) -> Result<(), String>;
+    fn delete(&self, eve_character_id: u64) -> Result<(), String>;
+    fn list(&self) -> Result<Vec<u64>, String>;
+    /// Characters owned by the given Discord user.
+    fn list_by_discord_user(&self, discord_character_id: u64) -> Result<Vec<u64>, String>;
+    /// Characters monitored in the given Discord guild.
+    fn list_by_discord_guild(&self, discord_guild_id: u64) -> Result<Vec<u64>, String>;
+    /// Remove any older copy of a character kept by the backend, for example after re-encrypting it.
+    fn remove_backup(&self, _eve_character_id: u64) -> Result<(), String> {
+        Ok(())
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum StorageKind {
+    /* One char-<id>.json per character. */
+    Json,
+    /* All characters in characters.sqlite. */
+    Sqlite,
+}
+
+impl StorageKind {
+    pub fn parse(kind: &str) -> Result<Self, String> {
+        match kind {
+            "json" => Ok(StorageKind::Json),
+            "sqlite" => Ok(StorageKind::Sqlite),
+            kind => Err(format!("Unknown storage backend {}", kind)),
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct StorageConfig {
+    pub folder: String,
+    pub kind: StorageKind,
+    pub key: StorageKey,
+}
+
+impl StorageConfig {
+    pub fn from_env() -> Result<Self, String> {
+        Ok(Self {
+            folder: env::var("STORAGE_FOLDER")
+                .map_err(|_| "Expected STORAGE_FOLDER in the environment")?,
+            kind: match env::var("STORAGE_BACKEND") {
+                Ok(kind) => StorageKind::parse(&kind)?,
+                Err(_) => StorageKind::Json,
+            },
+            key: StorageKey::from_env()?,
+        })
+    }
+}
+
+/// Open the configured storage; a new SQLite database imports any existing JSON files.
+pub fn open_storage(config: &StorageConfig) -> Result<Box<dyn StorageBackend>, String> {
+    match config.kind {
+        StorageKind::Json => Ok(Box::new(JsonStorage::new(
+            &config.folder,
+            config.key.clone(),
+        ))),
+        StorageKind::Sqlite => Ok(Box::new(SqliteStorage::open(
+            &config.folder,
+            config.key.clone(),
+        )?)),
+    }
+}
+
+/// Re-encrypt all characters with a new key.
+///
+/// Characters already using the new key are skipped, so an interrupted rotation can simply be
+/// run again. Refuses to run while the bot uses the storage; it would keep writing with the old key.
+pub fn rotate_storage_key(config: &StorageConfig, new_key: StorageKey) -> Result<usize, String> {
+    let _lock = StorageLock::acquire(&config.folder)?;
+
+    let old_storage = open_storage(config)?;
+    let new_storage = open_storage(&StorageConfig {
+        key: new_key,
+        ..config.clone()
+    })?;
+
+    let mut rotated = 0;
+    for eve_character_id in old_storage.list()? {
+        match old_storage.read(eve_character_id) {
+            Ok(storage) => {
+                new_storage.write(&storage)?;
+                /* A backup would still be readable with the old key. */
+                new_storage.remove_backup(eve_character_id)?;
+                rotated += 1;
+            }
+            Err(error) => {
+                if new_storage.read(eve_character_id).is_err() {
+                    return Err(format!("[{}] {}", eve_character_id, error));
+                }
+            }
+        }
+    }
+
+    /* The SQLite backend doesn't read the JSON files any more; those could still hold old tokens. */
+    let plain_text = json::plain_text_files(&config.folder)?;
+    if !plain_text.is_empty() {
+        return Err(format!(
+            "These files still hold refresh tokens in plain text; delete them and run again: {}",
+            plain_text.join(", ")
+        ));
+    }
+
+    info!("Re-encrypted {} characters with the new key", rotated);
+    Ok(rotated)
+}
+
+fn serialize(storage: &StorageV2, key: &StorageKey) -> String {
+    let storage = StorageV2 {
+        refresh_token: key.encrypt(&storage.refresh_token),
+        ..storage.clone()
+    };
+
+    serde_json::to_string(&Storage::V2(storage)).unwrap()
+}
+
+/* Only fails if the data itself is broken; see `decode` for the rest. */
+fn parse(data: &str) -> Result<Storage, String> {
+    serde_json::from_str(data).map_err(|e| format!("Invalid storage: {}", e))
+}
+
+/// Turn a stored character into the current format, and tell whether it needs to be written back.
+fn decode(storage: Storage, key: &StorageKey) -> Result<(StorageV2, bool), String> {
+    let upgraded = !storage.is_current();
+
+    Ok((storage.decrypt(key)?.migrate(), upgraded))
+}
+
+impl Monitor {
+    pub fn read_from_storage(&self, eve_character_id: u64) -> Result<StorageV2, String> {
+        self.storage.read(eve_character_id)
+    }
+
+    pub fn delete_from_storage(&self, eve_character_id: u64) -> Result<(), String> {
+        self.storage.delete(eve_character_id)?;
+        self.index_remove(eve_character_id);
+
+        Ok(())
+    }
+
+    pub fn list_storage(&self) -> Vec<u64> {
+        match self.storage.list() {
+            Ok(eve_character_ids) => eve_character_ids,
+            Err(error) => {
+                error!("Failed to list storage: {}", error);
+                Vec::new()
+            }
+        }
+    }
+
+    pub fn write_to_storage(&self, eve_character_id: u64, storage: StorageV2) {
+        if let Err(error) = self.storage.write(&storage) {
+            error!("[{}] Failed to write storage: {}", eve_character_id, error);
+            return;
+        }
+
+        self.index_update(&storage);
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    pub async fn create_eve_character(
+        &self,
+        refresh_token: String,
+        eve_character_id: u64,
+        eve_character_name: String,
+        scopes: Vec<String>,
+        discord_character_id: u64,
+        discord_guild_id: u64,
+        discord_channel_id: u64,
+        discord_activity_thread_id: u64,
+        discord_status_message_id: u64,
+    ) {
+        let storage = StorageV2 {
+            refresh_token,
+            expired: false,
+            eve_character_id,
+            eve_character_name,
+            scopes,
+            discord_character_id,
+            discord_guild_id,
+            discord_channel_id,
+            discord_activity_thread_id,
+            discord_status_message_id: Some(discord_status_message_id),
+            discord_status_hash: None,
+            skills: Vec::new(),
+            skill_queue: Vec::new(),
+            total_sp: 0,
+            unallocated_sp: 0,
+            history: SpHistory::default(),
+            queue_warnings: Vec::new(),
+        };
+
+        self.write_to_storage(eve_character_id, storage);
+        self.queue_eve_character(eve_character_id).await;
+    }
+
+    pub async fn refresh_eve_character(
+        &self,
+        eve_character_id: u64,
+        refresh_token: &str,
+        scopes: &[String],
+    ) -> Result<u64, String> {
+        let storage = self.read_from_storage(eve_character_id)?;
+
+        if !storage.expired {
+            return Err("This character is already monitored.".to_string());
+        }
+
+        let storage = StorageV2 {
+            refresh_token: refresh_token.to_string(),
+            expired: false,
+            scopes: scopes.to_vec(),
+            ..storage
+        };
+
+        let discord_channel_id = storage.discord_channel_id;
+
+        /* The channel might have been archived when monitoring stopped. */
+        if let Err(error) = self
+            .bot
+            .discord_unarchive_channel(discord_channel_id, storage.discord_activity_thread_id)
+            .await
+        {
+            warn!(
+                "[{}] Failed to unarchive channel: {}",
+                eve_character_id, error
+            );
+        }
+
+        self.write_to_storage(eve_character_id, storage);
+        self.queue_eve_character(eve_character_id).await;
+        Ok(discord_channel_id)
+    }
+}
@@ -0,0 +1,214 @@
+use std::sync::Mutex;
+
+use rusqlite::{params, Connection, OptionalExtension, Params, Transaction};
+use tracing::{info, warn};
+
+use super::{decode, parse, serialize, JsonStorage, StorageBackend, StorageKey, StorageV2};
+
+/* Bumped whenever the schema changes; 0 is a database that was just created. */
+const SCHEMA_VERSION: i32 = 1;
+
+/// All characters in a single SQLite database, `characters.sqlite` in the storage folder.
+pub struct SqliteStorage {
+    connection: Mutex<Connection>,
+    key: StorageKey,
+}
+
+fn create_schema(transaction: &Transaction) -> rusqlite::Result<()> {
+    transaction.execute_batch(
+        "CREATE TABLE characters (
+            eve_character_id INTEGER PRIMARY KEY,
+            discord_character_id INTEGER NOT NULL,
+            discord_guild_id INTEGER NOT NULL,
+            data TEXT NOT NULL
+        );
+        CREATE INDEX characters_discord_character_id ON characters (discord_character_id);
+        CREATE INDEX characters_discord_guild_id ON characters (discord_guild_id);",
+    )
+}
+
+fn upsert(
+    transaction: &Transaction,
+    storage: &StorageV2,
+    key: &StorageKey,
+) -> rusqlite::Result<()> {
+    transaction.execute(
+        "INSERT INTO characters (eve_character_id, discord_character_id, discord_guild_id, data)
+         VALUES (?1, ?2, ?3, ?4)
+         ON CONFLICT (eve_character_id) DO UPDATE SET
+            discord_character_id = excluded.discord_character_id,
+            discord_guild_id = excluded.discord_guild_id,
+            data = excluded.data",
+        params![
+            storage.eve_character_id,
+            storage.discord_character_id,
+            storage.discord_guild_id,
+            serialize(storage, key),
+        ],
+    )?;
+
+    Ok(())
+}
+
+/* One-shot import of the char-<id>.json files from before the database existed. The files are only
+ * read; reading them through JsonStorage would upgrade or quarantine them. Returns the number of
+ * characters imported, and those whose file still holds a plain text token. */
+fn import_json(
+    transaction: &Transaction,
+    folder: &str,
+    key: &StorageKey,
+) -> Result<(usize, Vec<StorageV2>), String> {
+    let json = JsonStorage::new(folder, key.clone());
+
+    let mut imported = 0;
+    let mut plain_text = Vec::new();
+    for eve_character_id in json.list()? {
+        let path = format!("{}/char-{}.json", folder, eve_character_id);
+        let storage = std::fs::read_to_string(&path)
+            .map_err(|e| e.to_string())
+            .and_then(|data| parse(&data))
+            .and_then(|storage| decode(storage, key));
+
+        match storage {
+            Ok((storage, upgraded)) => {
+                upsert(transaction, &storage, key).map_err(|e| e.to_string())?;
+                imported += 1;
+                if upgraded {
+                    plain_text.push(storage);
+                }
+            }
+            Err(error) => warn!(
+                "[{}] Not importing character from JSON storage: {}",
+                eve_character_id, error
+            ),
+        }
+    }
+
+    Ok((imported, plain_text))
+}
+
+impl SqliteStorage {
+    pub fn open(folder: &str, key: StorageKey) -> Result<Self, String> {
+        let path = format!("{}/characters.sqlite", folder);
+        let mut connection = Connection::open(&path).map_err(|e| format!("{}: {}", path, e))?;
+
+        let version: i32 = connection
+            .query_row("PRAGMA user_version", [], |row| row.get(0))
+            .map_err(|e| e.to_string())?;
+
+        if version == 0 {
+            /* Create and fill the database in one go; a failed import leaves nothing behind. */
+            let transaction = connection.transaction().map_err(|e| e.to_string())?;
+            create_schema(&transaction).map_err(|e| e.to_string())?;
+            let (imported, plain_text) = import_json(&transaction, folder, &key)?;
+            transaction
+                .pragma_update(None, "user_version", SCHEMA_VERSION)
+                .map_err(|e| e.to_string())?;
+            transaction.commit().map_err(|e| e.to_string())?;
+
+            info!(
+                "Created {}, importing {} characters from JSON storage",
+                path, imported
+            );
+
+            /* Now safely in the database; don't leave plain text tokens behind in the old files. */
+            let json = JsonStorage::new(folder, key.clone());
+            for storage in plain_text {
+                let eve_character_id = storage.eve_character_id;
+                if let Err(error) = json
+                    .write(&storage)
+                    .and_then(|_| json.remove_backup(eve_character_id))
+                {
+                    warn!(
+                        "[{}] Failed to encrypt the token in JSON storage: {}",
+                        eve_character_id, error
+                    );
+                }
+            }
+        } else if version != SCHEMA_VERSION {
+            return Err(format!("{}: unknown schema version {}", path, version));
+        }
+
+        Ok(Self {
+            connection: Mutex::new(connection),
+            key,
+        })
+    }
+
+    fn list_query(&self, query: &str, params: impl Params) -> Result<Vec<u64>, String> {
+        let connection = self.connection.lock().unwrap();
+
+        let mut statement = connection.prepare(query).map_err(|e| e.to_string())?;
+        let rows = statement
+            .query_map(params, |row| row.get(0))
+            .map_err(|e| e.to_string())?;
+
+        rows.collect::<rusqlite::Result<Vec<u64>>>()
+            .map_err(|e| e.to_string())
+    }
+}
+
+impl StorageBackend for SqliteStorage {
+    fn read(&self, eve_character_id: u64) -> Result<StorageV2, String> {
+        let data: Option<String> = self
+            .connection
+            .lock()
+            .unwrap()
+            .query_row(
+                "SELECT data FROM characters WHERE eve_character_id = ?1",
+                [eve_character_id],
+                |row| row.get(0),
+            )
+            .optional()
+            .map_err(|e| e.to_string())?;
+
+        let (storage, upgraded) = decode(parse(&data.ok_or("Character not found.")?)?, &self.key)?;
+        if upgraded {
+            self.write(&storage)?;
+        }
+
+        Ok(storage)
+    }
+
+    fn write(&self, storage: &StorageV2) -> Result<(), String> {
+        let mut connection = self.connection.lock().unwrap();
+
+        let transaction = connection.transaction().map_err(|e| e.to_string())?;
+        upsert(&transaction, storage, &self.key).map_err(|e| e.to_string())?;
+        transaction.commit().map_err(|e| e.to_string())
+    }
+
+    fn delete(&self, eve_character_id: u64) -> Result<(), String> {
+        let connection = self.connection.lock().unwrap();
+
+        let deleted = connection
+            .execute(
+                "DELETE FROM characters WHERE eve_character_id = ?1",
+                [eve_character_id],
+            )
+            .map_err(|e| e.to_string())?;
+
+        match deleted {
+            0 => Err("Character not found.".to_string()),
+            _ => Ok(()),
+        }
+    }
+
+    fn list(&self) -> Result<Vec<u64>, String> {
+        self.list_query("SELECT eve_character_id FROM characters", [])
+    }
+
+    fn list_by_discord_user(&self, discord_character_id: u64) -> Result<Vec<u64>, String> {
+        self.list_query(
+            "SELECT eve_character_id FROM characters WHERE discord_character_id = ?1",
+            [discord_character_id],
+        )
+    }
+
+    fn list_by_discord_guild(&self, discord_guild_id: u64) -> Result<Vec<u64>, String> {
+        self.list_query(
+            "SELECT eve_character_id FROM characters WHERE discord_guild_id = ?1",
+            [discord_guild_id],
+        )
+    }
+}
@@ -0,0 +1,103 @@
+use tracing::{info, warn};
+
+use super::{storage::StorageV2, Monitor};
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ChannelAction {
+    Keep,
+    Archive,
+    Delete,
+}
+
+impl Monitor {
+    pub fn find_eve_characters(&self, discord_character_id: u64) -> Vec<StorageV2> {
+        self.index_find(discord_character_id)
+            .into_iter()
+            .filter_map(|entry| self.read_from_storage(entry.eve_character_id).ok())
+            .collect()
+    }
+
+    pub async fn uninstall(
+        &self,
+        eve_character_id: u64,
+        discord_character_id: u64,
+        delete: bool,
+        channel_action: ChannelAction,
+    ) -> Result<(), String> {
+        /* Hold the list while reading and writing; a refresh in flight writes the rotated token
+         * under it, and no longer writes once the character is removed. */
+        let mut eve_character_list = self.eve_character_list.lock().await;
+        let storage = self.read_from_storage(eve_character_id)?;
+
+        /* Only the Discord user who installed the character can stop monitoring it. */
+        if storage.discord_character_id != discord_character_id {
+            return Err("Character not found.".to_string());
+        }
+
+        info!(
+            "[{}] Stopping monitoring as requested by Discord ID {}",
+            eve_character_id, discord_character_id
+        );
+
+        eve_character_list.remove(&eve_character_id);
+
+        /* Store the outcome first; whatever fails below, the character is not loaded again. Without
+         * a channel, there is nothing to return to on re-authentication. */
+        if delete || channel_action == ChannelAction::Delete {
+            self.delete_from_storage(eve_character_id)?;
+        } else {
+            self.write_to_storage(
+                eve_character_id,
+                StorageV2 {
+                    expired: true,
+                    ..storage.clone()
+                },
+            );
+        }
+        drop(eve_character_list);
+
+        /* The token might already be invalid (for example, for expired characters); that is fine. */
+        if let Err(error) = self
+            .bot
+            .revoke_refresh_token(storage.refresh_token.clone())
+            .await
+        {
+            warn!("[{}] Failed to revoke token: {}", eve_character_id, error);
+        }
+
+        let result = match channel_action {
+            ChannelAction::Keep => Ok(()),
+            ChannelAction::Archive => {
+                let _ = self
+                    .bot
+                    .discord_send_message(
+                        storage.discord_activity_thread_id,
+                        &format!(
+                            "<@{}>: Monitoring stopped. Use /monitor to start monitoring this character again.",
+                            storage.discord_character_id
+                        ),
+                    )
+                    .await;
+                self.bot
+                    .discord_archive_channel(
+                        storage.discord_channel_id,
+                        storage.discord_activity_thread_id,
+                    )
+                    .await
+            }
+            ChannelAction::Delete => {
+                self.bot
+                    .discord_delete_channel(storage.discord_channel_id)
+                    .await
+            }
+        };
+        if let Err(error) = result {
+            warn!(
+                "[{}] Failed to clean up the channel: {}",
+                eve_character_id, error
+            );
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,77 @@
+use chrono::{DateTime, TimeDelta, Utc};
+
+use crate::esi::EsiSkillQueueItem;
+
+use super::{storage::StorageV2, Monitor};
+
+/// Parse warning thresholds like "24h,1h"; "off" (or nothing) disables the warnings.
+pub fn parse_thresholds(thresholds: &str) -> Result<Vec<TimeDelta>, String> {
+    if thresholds.trim().is_empty() || thresholds.trim() == "off" {
+        return Ok(Vec::new());
+    }
+
+    let mut result = Vec::new();
+    for threshold in thresholds.split(',') {
+        let threshold = threshold.trim();
+        let (amount, unit) = threshold.split_at(threshold.len().saturating_sub(1));
+        let amount: i64 = amount
+            .parse()
+            .map_err(|_| format!("Invalid warning threshold {}", threshold))?;
+
+        result.push(match unit {
+            "d" => TimeDelta::days(amount),
+            "h" => TimeDelta::hours(amount),
+            "m" => TimeDelta::minutes(amount),
+            _ => return Err(format!("Invalid warning threshold {}", threshold)),
+        });
+    }
+
+    /* Longest first, so the first threshold reached is also the first in the list. */
+    result.sort_by(|a, b| b.cmp(a));
+    Ok(result)
+}
+
+/// When the queue runs empty; None if it is empty already, or paused.
+pub fn queue_end(skill_queue: &[EsiSkillQueueItem]) -> Option<DateTime<Utc>> {
+    skill_queue.last()?.finish_date
+}
+
+impl Monitor {
+    /// A warning if the queue crossed a threshold it wasn't warned about yet.
+    pub(super) fn queue_warning(
+        &self,
+        storage: &mut StorageV2,
+        now: DateTime<Utc>,
+    ) -> Option<String> {
+        let queue_end = queue_end(&storage.skill_queue)?;
+        let remaining = queue_end - now;
+
+        /* Thresholds that no longer apply, because the queue was extended, can fire again. */
+        storage
+            .queue_warnings
+            .retain(|threshold| remaining <= TimeDelta::minutes(*threshold));
+
+        if remaining <= TimeDelta::zero() {
+            return None;
+        }
+
+        /* If several thresholds were crossed at once, warn only once. */
+        let crossed: Vec<i64> = self
+            .queue_warnings
+            .iter()
+            .filter(|threshold| remaining <= **threshold)
+            .map(|threshold| threshold.num_minutes())
+            .filter(|threshold| !storage.queue_warnings.contains(threshold))
+            .collect();
+        if crossed.is_empty() {
+            return None;
+        }
+        storage.queue_warnings.extend(crossed);
+
+        Some(format!(
+            "<@{}>: Skill queue will run empty <t:{}:R>.",
+            storage.discord_character_id,
+            queue_end.timestamp()
+        ))
+    }
+}
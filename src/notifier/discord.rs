@@ -0,0 +1,272 @@
+use std::sync::Arc;
+
+use serenity::all::{
+    Builder, ChannelId, ChannelType, CreateChannel, CreateEmbed, CreateEmbedFooter, CreateThread,
+    EditInteractionResponse, EditMessage, EditThread, GetMessages, Guild, GuildId, Http, HttpError,
+    MessageId, PermissionOverwrite, PermissionOverwriteType, Permissions, UserId,
+};
+use serenity::async_trait;
+
+use super::{Embed, Notifier};
+
+const BOT_USER_ID: u64 = 1251447020741464116;
+
+fn permissions_both() -> Permissions {
+    Permissions::VIEW_CHANNEL | Permissions::READ_MESSAGE_HISTORY
+}
+
+fn permissions_bot() -> Permissions {
+    Permissions::SEND_MESSAGES
+        | Permissions::SEND_MESSAGES_IN_THREADS
+        | Permissions::CREATE_PUBLIC_THREADS
+        | Permissions::CREATE_PRIVATE_THREADS
+        | Permissions::MANAGE_MESSAGES
+}
+
+/// Talks to Discord via the serenity HTTP client.
+pub struct SerenityNotifier {
+    http: Arc<Http>,
+}
+
+impl SerenityNotifier {
+    pub fn new(http: Arc<Http>) -> Self {
+        Self { http }
+    }
+}
+
+#[async_trait]
+impl Notifier for SerenityNotifier {
+    async fn create_private_channel(
+        &self,
+        guild_id: u64,
+        category_id: u64,
+        character_id: u64,
+        name: &str,
+    ) -> Result<(u64, u64), String> {
+        let http = &self.http;
+
+        let guild = Guild::get(http, guild_id).await.unwrap();
+
+        let everyone = GuildId::everyone_role(&guild.id);
+
+        /* EVE names can contains spaces or single quotation. Replace them with dashes. */
+        let slug = name.replace([' ', '\''], "-");
+
+        let permissions_both = permissions_both();
+        let permissions_bot = permissions_bot();
+
+        /* Make it a private channel where only the bot can speak, and the user can read. */
+        let permissions = vec![
+            PermissionOverwrite {
+                allow: permissions_both | permissions_bot,
+                deny: Permissions::empty(),
+                kind: PermissionOverwriteType::Member(UserId::new(BOT_USER_ID)),
+            },
+            PermissionOverwrite {
+                allow: permissions_both,
+                deny: permissions_bot,
+                kind: PermissionOverwriteType::Member(UserId::new(character_id)),
+            },
+            PermissionOverwrite {
+                allow: Permissions::empty(),
+                deny: Permissions::VIEW_CHANNEL,
+                kind: PermissionOverwriteType::Role(everyone),
+            },
+        ];
+
+        let builder = CreateChannel::new(slug)
+            .kind(ChannelType::Text)
+            .category(category_id)
+            .topic(format!("Skill training status of {}.", name))
+            .permissions(permissions);
+        let channel = guild
+            .create_channel(http, builder)
+            .await
+            .map_err(|e| e.to_string())?;
+
+        let thread = channel
+            .create_thread(
+                http,
+                CreateThread::new("Activity")
+                    .auto_archive_duration(serenity::all::AutoArchiveDuration::OneWeek)
+                    .kind(ChannelType::PublicThread),
+            )
+            .await
+            .map_err(|e| e.to_string())?;
+        thread
+            .say(
+                http,
+                format!(
+                    "<@{}>: here I will let you know when a skill training finished.",
+                    character_id
+                ),
+            )
+            .await
+            .map_err(|e| e.to_string())?;
+
+        Ok((channel.id.get(), thread.id.get()))
+    }
+
+    async fn send_message(&self, channel_id: u64, message: &str) -> Result<u64, String> {
+        let http = &self.http;
+
+        let channel_id = ChannelId::new(channel_id);
+        let message = channel_id
+            .say(http, message)
+            .await
+            .map_err(|e| e.to_string())?;
+
+        Ok(message.id.get())
+    }
+
+    async fn edit_message(
+        &self,
+        channel_id: u64,
+        message_id: u64,
+        message: &str,
+        embed: &Embed,
+    ) -> Result<bool, String> {
+        let http = &self.http;
+
+        let channel_id = ChannelId::new(channel_id);
+        let result = channel_id
+            .edit_message(
+                http,
+                MessageId::new(message_id),
+                EditMessage::new()
+                    .content(message)
+                    .embed(create_embed(embed)),
+            )
+            .await;
+
+        match result {
+            Ok(_) => Ok(true),
+            /* Unknown Message; it was deleted. */
+            Err(serenity::Error::Http(HttpError::UnsuccessfulRequest(response)))
+                if response.error.code == 10008 =>
+            {
+                Ok(false)
+            }
+            Err(error) => Err(error.to_string()),
+        }
+    }
+
+    async fn pin_message(&self, channel_id: u64, message_id: u64) -> Result<(), String> {
+        let http = &self.http;
+
+        let channel_id = ChannelId::new(channel_id);
+        channel_id
+            .pin(http, MessageId::new(message_id))
+            .await
+            .map_err(|e| e.to_string())?;
+
+        Ok(())
+    }
+
+    async fn last_own_message(&self, channel_id: u64) -> Result<Option<u64>, String> {
+        let http = &self.http;
+
+        let channel_id = ChannelId::new(channel_id);
+        let messages = channel_id
+            .messages(http, GetMessages::new().limit(1))
+            .await
+            .map_err(|e| e.to_string())?;
+
+        Ok(messages
+            .first()
+            .filter(|message| message.author.id == UserId::new(BOT_USER_ID))
+            .map(|message| message.id.get()))
+    }
+
+    async fn archive_channel(&self, channel_id: u64, thread_id: u64) -> Result<(), String> {
+        let http = &self.http;
+
+        let thread_id = ChannelId::new(thread_id);
+        thread_id
+            .edit_thread(http, EditThread::new().archived(true).locked(true))
+            .await
+            .map_err(|e| e.to_string())?;
+
+        /* Nobody can post anymore; the history stays readable. */
+        let channel_id = ChannelId::new(channel_id);
+        channel_id
+            .create_permission(
+                http,
+                PermissionOverwrite {
+                    allow: permissions_both(),
+                    deny: permissions_bot(),
+                    kind: PermissionOverwriteType::Member(UserId::new(BOT_USER_ID)),
+                },
+            )
+            .await
+            .map_err(|e| e.to_string())?;
+
+        Ok(())
+    }
+
+    async fn unarchive_channel(&self, channel_id: u64, thread_id: u64) -> Result<(), String> {
+        let http = &self.http;
+
+        let channel_id = ChannelId::new(channel_id);
+        channel_id
+            .create_permission(
+                http,
+                PermissionOverwrite {
+                    allow: permissions_both() | permissions_bot(),
+                    deny: Permissions::empty(),
+                    kind: PermissionOverwriteType::Member(UserId::new(BOT_USER_ID)),
+                },
+            )
+            .await
+            .map_err(|e| e.to_string())?;
+
+        let thread_id = ChannelId::new(thread_id);
+        thread_id
+            .edit_thread(http, EditThread::new().archived(false).locked(false))
+            .await
+            .map_err(|e| e.to_string())?;
+
+        Ok(())
+    }
+
+    async fn delete_channel(&self, channel_id: u64) -> Result<(), String> {
+        let http = &self.http;
+
+        let channel_id = ChannelId::new(channel_id);
+        channel_id.delete(http).await.map_err(|e| e.to_string())?;
+
+        Ok(())
+    }
+
+    async fn edit_interaction_response(&self, token: &str, message: &str) -> Result<(), String> {
+        let http = &self.http;
+
+        EditInteractionResponse::new()
+            .content(message)
+            .execute(http.as_ref(), token)
+            .await
+            .map_err(|e| e.to_string())?;
+
+        Ok(())
+    }
+}
+
+fn create_embed(embed: &Embed) -> CreateEmbed {
+    let mut builder = CreateEmbed::new()
+        .title(&embed.title)
+        .description(&embed.description)
+        .colour(embed.colour)
+        .fields(
+            embed
+                .fields
+                .iter()
+                .map(|field| (&field.name, &field.value, false)),
+        )
+        .footer(CreateEmbedFooter::new(&embed.footer))
+        .timestamp(embed.timestamp);
+    if let Some(thumbnail) = &embed.thumbnail {
+        builder = builder.thumbnail(thumbnail);
+    }
+
+    builder
+}
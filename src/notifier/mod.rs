@@ -0,0 +1,68 @@
+use chrono::{DateTime, Utc};
+use serenity::async_trait;
+
+mod discord;
+mod recording;
+
+pub use discord::SerenityNotifier;
+pub use recording::{Notification, RecordingNotifier};
+
+/// A rich message, independent of how Discord wants it sent.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Embed {
+    pub title: String,
+    pub description: String,
+    pub thumbnail: Option<String>,
+    pub colour: u32,
+    pub fields: Vec<EmbedField>,
+    pub footer: String,
+    pub timestamp: DateTime<Utc>,
+}
+
+#[derive(Clone, Debug, PartialEq, Hash)]
+pub struct EmbedField {
+    pub name: String,
+    pub value: String,
+}
+
+/// Everything the monitor posts to Discord goes through this.
+#[async_trait]
+pub trait Notifier: Send + Sync {
+    /// Create a channel only the character (and the bot) can see, with an activity thread in it.
+    async fn create_private_channel(
+        &self,
+        guild_id: u64,
+        category_id: u64,
+        character_id: u64,
+        name: &str,
+    ) -> Result<(u64, u64), String>;
+
+    /// Post a message, and return its ID.
+    async fn send_message(&self, channel_id: u64, message: &str) -> Result<u64, String>;
+
+    /// Replace a message; `message` is shown by clients that can't show embeds.
+    /// Returns false if the message no longer exists.
+    async fn edit_message(
+        &self,
+        channel_id: u64,
+        message_id: u64,
+        message: &str,
+        embed: &Embed,
+    ) -> Result<bool, String>;
+
+    async fn pin_message(&self, channel_id: u64, message_id: u64) -> Result<(), String>;
+
+    /// The ID of the last message in the channel, if the bot posted it.
+    async fn last_own_message(&self, channel_id: u64) -> Result<Option<u64>, String>;
+
+    /// Archive the activity thread, and make the channel read-only for the bot too.
+    async fn archive_channel(&self, channel_id: u64, thread_id: u64) -> Result<(), String>;
+
+    /// Undo `archive_channel`, so the bot can post again.
+    async fn unarchive_channel(&self, channel_id: u64, thread_id: u64) -> Result<(), String>;
+
+    async fn delete_channel(&self, channel_id: u64) -> Result<(), String>;
+
+    /// Replace the (ephemeral) response to a slash command, identified by its interaction token.
+    async fn edit_interaction_response(&self, token: &str, message: &str) -> Result<(), String>;
+}
@@ -0,0 +1,292 @@
+use std::collections::{HashMap, HashSet};
+use std::sync::Mutex;
+
+use serenity::async_trait;
+
+use super::{Embed, Notifier};
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Notification {
+    CreateChannel {
+        guild_id: u64,
+        character_id: u64,
+        name: String,
+        channel_id: u64,
+        thread_id: u64,
+    },
+    SendMessage {
+        channel_id: u64,
+        message_id: u64,
+        message: String,
+    },
+    EditMessage {
+        channel_id: u64,
+        message_id: u64,
+        message: String,
+        embed: Embed,
+    },
+    PinMessage {
+        channel_id: u64,
+        message_id: u64,
+    },
+    ArchiveChannel {
+        channel_id: u64,
+        thread_id: u64,
+    },
+    UnarchiveChannel {
+        channel_id: u64,
+        thread_id: u64,
+    },
+    DeleteChannel {
+        channel_id: u64,
+    },
+    EditInteractionResponse {
+        token: String,
+        message: String,
+    },
+}
+
+#[derive(Default)]
+struct RecordingState {
+    notifications: Vec<Notification>,
+    /* Per channel, the ID and content of each message. */
+    channels: HashMap<u64, Vec<(u64, String)>>,
+    embeds: HashMap<u64, Embed>,
+    pins: HashMap<u64, Vec<u64>>,
+    /* Messages posted by someone other than the bot. */
+    user_messages: HashSet<u64>,
+    /* Archived channels and threads; posting in them fails, like on Discord. */
+    archived: HashSet<u64>,
+    responses: HashMap<String, String>,
+    next_channel_id: u64,
+    next_message_id: u64,
+}
+
+/// Keeps everything in memory instead of talking to Discord; used to test the monitor.
+#[derive(Default)]
+pub struct RecordingNotifier(Mutex<RecordingState>);
+
+impl RecordingNotifier {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Everything that would have been sent to Discord, in order.
+    pub fn notifications(&self) -> Vec<Notification> {
+        self.0.lock().unwrap().notifications.clone()
+    }
+
+    /// Current content of all messages in a channel (or thread), oldest first.
+    pub fn messages(&self, channel_id: u64) -> Vec<String> {
+        let state = self.0.lock().unwrap();
+        state
+            .channels
+            .get(&channel_id)
+            .map(|messages| messages.iter().map(|(_, m)| m.clone()).collect())
+            .unwrap_or_default()
+    }
+
+    /// IDs of the pinned messages in a channel.
+    pub fn pins(&self, channel_id: u64) -> Vec<u64> {
+        let state = self.0.lock().unwrap();
+        state.pins.get(&channel_id).cloned().unwrap_or_default()
+    }
+
+    /// Post a message as someone other than the bot, and return its ID.
+    pub fn post_as_user(&self, channel_id: u64, message: &str) -> u64 {
+        let mut state = self.0.lock().unwrap();
+
+        let message_id = state.next_message_id + 1;
+        state.next_message_id += 1;
+
+        state
+            .channels
+            .get_mut(&channel_id)
+            .unwrap()
+            .push((message_id, message.to_string()));
+        state.user_messages.insert(message_id);
+
+        message_id
+    }
+
+    /// Delete a message, as if someone removed it by hand.
+    pub fn delete_message(&self, channel_id: u64, message_id: u64) {
+        let mut state = self.0.lock().unwrap();
+        if let Some(messages) = state.channels.get_mut(&channel_id) {
+            messages.retain(|(id, _)| *id != message_id);
+        }
+        if let Some(pins) = state.pins.get_mut(&channel_id) {
+            pins.retain(|id| *id != message_id);
+        }
+    }
+
+    /// The embed last put on a message in a channel.
+    pub fn embed(&self, channel_id: u64) -> Option<Embed> {
+        self.0.lock().unwrap().embeds.get(&channel_id).cloned()
+    }
+
+    /// Current response to a slash command, identified by its interaction token.
+    pub fn response(&self, token: &str) -> Option<String> {
+        self.0.lock().unwrap().responses.get(token).cloned()
+    }
+}
+
+#[async_trait]
+impl Notifier for RecordingNotifier {
+    async fn create_private_channel(
+        &self,
+        guild_id: u64,
+        _category_id: u64,
+        character_id: u64,
+        name: &str,
+    ) -> Result<(u64, u64), String> {
+        let mut state = self.0.lock().unwrap();
+
+        let channel_id = state.next_channel_id + 1;
+        let thread_id = state.next_channel_id + 2;
+        state.next_channel_id += 2;
+
+        state.channels.insert(channel_id, Vec::new());
+        state.channels.insert(thread_id, Vec::new());
+        state.notifications.push(Notification::CreateChannel {
+            guild_id,
+            character_id,
+            name: name.to_string(),
+            channel_id,
+            thread_id,
+        });
+
+        Ok((channel_id, thread_id))
+    }
+
+    async fn send_message(&self, channel_id: u64, message: &str) -> Result<u64, String> {
+        let mut state = self.0.lock().unwrap();
+
+        if state.archived.contains(&channel_id) {
+            return Err("Channel is archived".to_string());
+        }
+
+        let message_id = state.next_message_id + 1;
+        state.next_message_id += 1;
+
+        state
+            .channels
+            .get_mut(&channel_id)
+            .ok_or("Unknown channel".to_string())?
+            .push((message_id, message.to_string()));
+        state.notifications.push(Notification::SendMessage {
+            channel_id,
+            message_id,
+            message: message.to_string(),
+        });
+
+        Ok(message_id)
+    }
+
+    async fn edit_message(
+        &self,
+        channel_id: u64,
+        message_id: u64,
+        message: &str,
+        embed: &Embed,
+    ) -> Result<bool, String> {
+        let mut state = self.0.lock().unwrap();
+
+        let existing = state
+            .channels
+            .get_mut(&channel_id)
+            .ok_or("Unknown channel".to_string())?
+            .iter_mut()
+            .find(|(id, _)| *id == message_id);
+        let Some((_, existing)) = existing else {
+            return Ok(false);
+        };
+        *existing = message.to_string();
+        state.embeds.insert(channel_id, embed.clone());
+        state.notifications.push(Notification::EditMessage {
+            channel_id,
+            message_id,
+            message: message.to_string(),
+            embed: embed.clone(),
+        });
+
+        Ok(true)
+    }
+
+    async fn pin_message(&self, channel_id: u64, message_id: u64) -> Result<(), String> {
+        let mut state = self.0.lock().unwrap();
+
+        state.pins.entry(channel_id).or_default().push(message_id);
+        state.notifications.push(Notification::PinMessage {
+            channel_id,
+            message_id,
+        });
+
+        Ok(())
+    }
+
+    async fn last_own_message(&self, channel_id: u64) -> Result<Option<u64>, String> {
+        let state = self.0.lock().unwrap();
+
+        let messages = state
+            .channels
+            .get(&channel_id)
+            .ok_or("Unknown channel".to_string())?;
+        Ok(messages
+            .last()
+            .map(|(id, _)| *id)
+            .filter(|id| !state.user_messages.contains(id)))
+    }
+
+    async fn archive_channel(&self, channel_id: u64, thread_id: u64) -> Result<(), String> {
+        let mut state = self.0.lock().unwrap();
+
+        state.archived.extend([channel_id, thread_id]);
+        state.notifications.push(Notification::ArchiveChannel {
+            channel_id,
+            thread_id,
+        });
+
+        Ok(())
+    }
+
+    async fn unarchive_channel(&self, channel_id: u64, thread_id: u64) -> Result<(), String> {
+        let mut state = self.0.lock().unwrap();
+
+        state.archived.remove(&channel_id);
+        state.archived.remove(&thread_id);
+        state.notifications.push(Notification::UnarchiveChannel {
+            channel_id,
+            thread_id,
+        });
+
+        Ok(())
+    }
+
+    async fn delete_channel(&self, channel_id: u64) -> Result<(), String> {
+        let mut state = self.0.lock().unwrap();
+
+        state.channels.remove(&channel_id);
+        state
+            .notifications
+            .push(Notification::DeleteChannel { channel_id });
+
+        Ok(())
+    }
+
+    async fn edit_interaction_response(&self, token: &str, message: &str) -> Result<(), String> {
+        let mut state = self.0.lock().unwrap();
+
+        state
+            .responses
+            .insert(token.to_string(), message.to_string());
+        state
+            .notifications
+            .push(Notification::EditInteractionResponse {
+                token: token.to_string(),
+                message: message.to_string(),
+            });
+
+        Ok(())
+    }
+}
@@ -1,52 +1,75 @@
-use crate::esi::{EsiSkillQueue, EsiSkills};
+use std::collections::HashMap;
+
+use crate::esi::{Claims, EsiResponse, EsiSkillQueue, EsiSkills};
 
 use super::BotState;
 
 impl BotState {
     pub async fn exchange_code(&self, code: String) -> Result<(String, String), String> {
-        let this = self.0.read().await;
+        let esi = self.esi().await;
 
-        this.esi.exchange_code(code).await
+        esi.exchange_code(code).await
     }
 
     pub async fn exchange_refresh_token(
         &self,
         refresh_token: String,
     ) -> Result<(String, String), String> {
-        let this = self.0.read().await;
+        let esi = self.esi().await;
+
+        esi.exchange_refresh_token(refresh_token).await
+    }
 
-        this.esi.exchange_refresh_token(refresh_token).await
+    pub async fn revoke_refresh_token(&self, refresh_token: String) -> Result<(), String> {
+        let esi = self.esi().await;
+
+        esi.revoke_refresh_token(refresh_token).await
+    }
+
+    pub async fn verify_jwt(&self, token: &str) -> Result<Claims, String> {
+        let esi = self.esi().await;
+
+        esi.verify_jwt(token).await
     }
 
     pub async fn get_authorization_url(&self, state: String) -> String {
-        let this = self.0.read().await;
+        let esi = self.esi().await;
 
-        this.esi.get_authorization_url(state).await
+        esi.get_authorization_url(state).await
     }
 
     pub async fn get_skills(
         &self,
-        access_token: &String,
+        access_token: &str,
         character_id: u64,
-    ) -> Result<EsiSkills, String> {
-        let this = self.0.read().await;
+    ) -> Result<EsiResponse<EsiSkills>, String> {
+        let esi = self.esi().await;
 
-        this.esi.get_skills(access_token, character_id).await
+        esi.get_skills(access_token, character_id).await
     }
 
     pub async fn get_skill_queue(
         &self,
-        access_token: &String,
+        access_token: &str,
         character_id: u64,
-    ) -> Result<EsiSkillQueue, String> {
-        let this = self.0.read().await;
+    ) -> Result<EsiResponse<EsiSkillQueue>, String> {
+        let esi = self.esi().await;
 
-        this.esi.get_skill_queue(access_token, character_id).await
+        esi.get_skill_queue(access_token, character_id).await
     }
 
-    pub async fn lookup_skill_name(&self, skill_id: i32) -> Result<String, String> {
-        let this = self.0.read().await;
+    pub async fn cached_skill_name(&self, skill_id: i32) -> Option<String> {
+        let esi = self.esi().await;
+
+        esi.cached_skill_name(skill_id).await
+    }
+
+    pub async fn lookup_skill_names(
+        &self,
+        skill_ids: &[i32],
+    ) -> Result<HashMap<i32, String>, String> {
+        let esi = self.esi().await;
 
-        this.esi.lookup_skill_name(skill_id).await
+        esi.lookup_skill_names(skill_ids).await
     }
 }
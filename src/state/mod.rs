@@ -2,11 +2,12 @@ use std::collections::HashMap;
 use std::sync::atomic::Ordering;
 use std::sync::{atomic::AtomicBool, Arc};
 
-use serenity::all::{CommandInteraction, Context, EditInteractionResponse};
+use serenity::all::Context;
 use tokio::sync::RwLock;
 
 use crate::esi::Esi;
 use crate::monitor::Monitor;
+use crate::notifier::{Notifier, SerenityNotifier};
 
 mod discord;
 mod esi;
@@ -15,14 +16,18 @@ mod pending;
 
 struct PendingState {
     expire_time: std::time::Instant,
-    interaction: CommandInteraction,
+    guild_id: u64,
+    user_id: u64,
+    /* Token of the slash command interaction, to edit its response. */
+    token: String,
 }
 
 struct BotStorage {
     startup: AtomicBool,
     pending: HashMap<String, PendingState>,
-    esi: Esi,
+    esi: Arc<Esi>,
     discord: Option<Context>,
+    notifier: Option<Arc<dyn Notifier>>,
     monitor: Option<Arc<Monitor>>,
 }
 
@@ -35,12 +40,13 @@ impl Clone for BotState {
 }
 
 impl BotState {
-    pub fn new(timeout_message: String) -> Self {
+    pub fn new(timeout_message: String, esi: Esi) -> Self {
         let store = BotState(Arc::new(RwLock::new(BotStorage {
             startup: AtomicBool::new(true),
             pending: HashMap::new(),
-            esi: Esi::new(),
+            esi: Arc::new(esi),
             discord: None,
+            notifier: None,
             monitor: None,
         })));
 
@@ -48,25 +54,31 @@ impl BotState {
         let store_clone = store.0.clone();
         tokio::spawn(async move {
             loop {
-                {
+                let expired = {
                     let mut store_clone = store_clone.write().await;
                     let now = std::time::Instant::now();
 
                     let keys: Vec<String> = store_clone.pending.keys().cloned().collect();
+                    let mut expired = Vec::new();
                     for key in keys {
                         let value = store_clone.pending.get(&key).unwrap();
                         if value.expire_time > now {
                             continue;
                         }
 
-                        let _ = value
-                            .interaction
-                            .edit_response(
-                                &store_clone.discord.as_ref().unwrap().http,
-                                EditInteractionResponse::new().content(&timeout_message),
-                            )
+                        expired.push(store_clone.pending.remove(&key).unwrap().token);
+                    }
+
+                    expired
+                };
+
+                /* Inform the user outside of the lock, as this talks to Discord. */
+                if !expired.is_empty() {
+                    let notifier = store_clone.read().await.notifier.clone().unwrap();
+                    for token in expired {
+                        let _ = notifier
+                            .edit_interaction_response(&token, &timeout_message)
                             .await;
-                        store_clone.pending.remove(&key);
                     }
                 }
 
@@ -77,12 +89,33 @@ impl BotState {
         store
     }
 
+    /* Clone out of the lock, so no network I/O happens while holding it. */
+    async fn esi(&self) -> Arc<Esi> {
+        self.0.read().await.esi.clone()
+    }
+
+    async fn monitor(&self) -> Arc<Monitor> {
+        self.0.read().await.monitor.clone().unwrap()
+    }
+
+    async fn notifier(&self) -> Arc<dyn Notifier> {
+        self.0.read().await.notifier.clone().unwrap()
+    }
+
     pub async fn set_discord(&self, ctx: Context) {
         let mut this = self.0.write().await;
 
+        this.notifier = Some(Arc::new(SerenityNotifier::new(ctx.http.clone())));
         this.discord = Some(ctx);
     }
 
+    /// Use another way to post to Discord; mostly useful to run the monitor without a gateway.
+    pub async fn set_notifier(&self, notifier: Arc<dyn Notifier>) {
+        let mut this = self.0.write().await;
+
+        this.notifier = Some(notifier);
+    }
+
     pub async fn set_monitor(&self, monitor: Arc<Monitor>) {
         let mut this = self.0.write().await;
 
@@ -1,4 +1,4 @@
-use crate::monitor::Monitor;
+use crate::monitor::{ChannelAction, IndexEntry, StorageV2};
 
 use super::BotState;
 
@@ -12,61 +12,85 @@ impl BotState {
         let (guild_id, character_id) = {
             let this = self.0.read().await;
             let pending = this.pending.get(state).unwrap();
-            (
-                pending.interaction.guild_id.unwrap().get(),
-                pending.interaction.user.id.get(),
-            )
+            (pending.guild_id, pending.user_id)
         };
 
-        Monitor::install(
-            self.clone(),
-            access_token,
-            refresh_token,
-            guild_id,
-            character_id,
-        )
-        .await
+        self.monitor()
+            .await
+            .install(access_token, refresh_token, guild_id, character_id)
+            .await
+    }
+
+    pub async fn uninstall_monitor(
+        &self,
+        eve_character_id: u64,
+        discord_character_id: u64,
+        delete: bool,
+        channel_action: ChannelAction,
+    ) -> Result<(), String> {
+        let monitor = self.monitor().await;
+        monitor
+            .uninstall(
+                eve_character_id,
+                discord_character_id,
+                delete,
+                channel_action,
+            )
+            .await
+    }
+
+    pub async fn find_eve_characters(&self, discord_character_id: u64) -> Vec<StorageV2> {
+        let monitor = self.monitor().await;
+        monitor.find_eve_characters(discord_character_id)
+    }
+
+    pub async fn index_find(&self, discord_character_id: u64) -> Vec<IndexEntry> {
+        let monitor = self.monitor().await;
+        monitor.index_find(discord_character_id)
     }
 
     pub async fn has_eve_character(&self, eve_character_id: u64) -> bool {
-        let this = self.0.read().await;
-        let monitor = this.monitor.as_ref().unwrap();
+        let monitor = self.monitor().await;
         monitor.has_eve_character(eve_character_id).await
     }
 
     pub async fn refresh_eve_character(
         &self,
         eve_character_id: u64,
-        refresh_token: &String,
+        refresh_token: &str,
+        scopes: &[String],
     ) -> Result<u64, String> {
-        let this = self.0.read().await;
-        let monitor = this.monitor.as_ref().unwrap();
+        let monitor = self.monitor().await;
         monitor
-            .refresh_eve_character(eve_character_id, refresh_token)
+            .refresh_eve_character(eve_character_id, refresh_token, scopes)
             .await
     }
 
+    #[allow(clippy::too_many_arguments)]
     pub async fn create_eve_character(
         &self,
         refresh_token: String,
         eve_character_id: u64,
         eve_character_name: String,
+        scopes: Vec<String>,
         discord_character_id: u64,
         discord_guild_id: u64,
         discord_channel_id: u64,
         discord_activity_thread_id: u64,
+        discord_status_message_id: u64,
     ) {
-        let this = self.0.read().await;
-        let monitor = this.monitor.as_ref().unwrap();
+        let monitor = self.monitor().await;
         monitor
             .create_eve_character(
                 refresh_token,
                 eve_character_id,
                 eve_character_name,
+                scopes,
                 discord_character_id,
                 discord_guild_id,
                 discord_channel_id,
                 discord_activity_thread_id,
+                discord_status_message_id,
             )
             .await;
     }
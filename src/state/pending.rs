@@ -1,14 +1,14 @@
-use serenity::all::{CommandInteraction, EditInteractionResponse};
-
 use super::{BotState, PendingState};
 
 impl BotState {
-    pub async fn pending_create(&self, key: String, value: &CommandInteraction) {
+    pub async fn pending_create(&self, key: String, guild_id: u64, user_id: u64, token: String) {
         self.0.write().await.pending.insert(
             key,
             PendingState {
                 expire_time: std::time::Instant::now() + std::time::Duration::from_secs(300),
-                interaction: value.clone(),
+                guild_id,
+                user_id,
+                token,
             },
         );
     }
@@ -17,16 +17,13 @@ impl BotState {
         self.0.read().await.pending.contains_key(key)
     }
 
-    pub async fn pending_edit_response(&self, key: &str, message: &String) {
-        let this = self.0.read().await;
-        let value = this.pending.get(key).unwrap();
+    pub async fn pending_edit_response(&self, key: &str, message: &str) {
+        let token = self.0.read().await.pending.get(key).unwrap().token.clone();
 
-        let _ = value
-            .interaction
-            .edit_response(
-                &this.discord.as_ref().unwrap().http,
-                EditInteractionResponse::new().content(message),
-            )
+        let _ = self
+            .notifier()
+            .await
+            .edit_interaction_response(&token, message)
             .await;
     }
 
@@ -53,7 +53,7 @@ async fn url_oauth_callback(
         tokio::spawn(async move {
             bot.pending_edit_response(
                 &query.state,
-                &format!("Authenticated successful. Creating channel ..."),
+                "Authenticated successful. Creating channel ...",
             )
             .await;
 
@@ -72,7 +72,7 @@ async fn url_oauth_callback(
                 Err(error) => {
                     bot.pending_edit_response(
                         &query.state,
-                        &format!("Failed to create channel: {}", error),
+                        &format!("Failed to monitor character: {}", error),
                     )
                     .await;
                 }
@@ -86,7 +86,7 @@ async fn url_oauth_callback(
         tokio::spawn(async move {
             bot.pending_edit_response(
                 &query.state,
-                &format!("Authentication failed. Use /monitor to try again."),
+                "Authentication failed. Use /monitor to try again.",
             )
             .await;
 
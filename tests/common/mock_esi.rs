@@ -0,0 +1,422 @@
+//! A small stand-in for EVE SSO and ESI, serving scripted responses.
+
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
+use std::sync::{Arc, Mutex};
+
+use axum::extract::{Form, Path, Request, State};
+use axum::http::{HeaderMap, StatusCode};
+use axum::middleware::{self, Next};
+use axum::response::{IntoResponse, Response};
+use axum::routing::{get, post};
+use axum::{Json, Router};
+use eve_skills_discord::esi::{EsiConfig, REQUIRED_SCOPES};
+use jsonwebtoken::{encode, Algorithm, EncodingKey, Header};
+use serde_json::{json, Value};
+
+pub const CLIENT_ID: &str = "mock-client-id";
+
+#[derive(Default)]
+struct MockCharacter {
+    name: String,
+    scopes: Vec<String>,
+    skills: Value,
+    skill_queue: Value,
+    /* Amount of upcoming skill / skill queue requests to answer with an error. */
+    failures: u32,
+}
+
+struct MockState {
+    base_url: String,
+    encoding_key: EncodingKey,
+    jwks: Value,
+    characters: HashMap<u64, MockCharacter>,
+    codes: HashMap<String, u64>,
+    access_tokens: HashMap<String, u64>,
+    refresh_tokens: HashMap<String, u64>,
+    names: HashMap<i32, String>,
+    requests: Vec<String>,
+    counter: u64,
+    /* Error budget (remaining errors, seconds till reset) to report on every response. */
+    error_limit: Option<(i64, u64)>,
+    /* Amount of upcoming requests to answer with this server error. */
+    server_errors: Option<(StatusCode, u32)>,
+}
+
+#[derive(Clone)]
+pub struct MockEsi {
+    pub base_url: String,
+    state: Arc<Mutex<MockState>>,
+}
+
+/* JWKs use unpadded URL-safe base64. */
+fn base64_url(data: &[u8]) -> String {
+    openssl::base64::encode_block(data)
+        .replace('+', "-")
+        .replace('/', "_")
+        .trim_end_matches('=')
+        .to_string()
+}
+
+impl MockState {
+    fn claims(&self, character_id: u64) -> Value {
+        let character = &self.characters[&character_id];
+
+        json!({
+            "sub": format!("CHARACTER:EVE:{}", character_id),
+            "name": character.name,
+            "scp": character.scopes,
+            "aud": [CLIENT_ID, "EVE Online"],
+            "iss": self.base_url,
+            "exp": chrono::Utc::now().timestamp() + 1200,
+        })
+    }
+
+    fn sign(&self, claims: &Value, kid: Option<&str>) -> String {
+        let mut header = Header::new(Algorithm::RS256);
+        header.kid = kid.map(|kid| kid.to_string());
+        encode(&header, claims, &self.encoding_key).unwrap()
+    }
+
+    fn issue_tokens(&mut self, character_id: u64) -> Value {
+        self.counter += 1;
+
+        let access_token = self.sign(&self.claims(character_id), Some("mock"));
+        let refresh_token = format!("refresh-{}-{}", character_id, self.counter);
+
+        self.access_tokens
+            .insert(access_token.clone(), character_id);
+        self.refresh_tokens
+            .insert(refresh_token.clone(), character_id);
+
+        json!({
+            "access_token": access_token,
+            "token_type": "Bearer",
+            "expires_in": 1199,
+            "refresh_token": refresh_token,
+        })
+    }
+
+    fn character_body(
+        &mut self,
+        headers: &HeaderMap,
+        character_id: u64,
+        body: impl Fn(&MockCharacter) -> Value,
+    ) -> Response {
+        let token = headers
+            .get("Authorization")
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.strip_prefix("Bearer "))
+            .unwrap_or_default();
+        if self.access_tokens.get(token) != Some(&character_id) {
+            return (StatusCode::FORBIDDEN, "token not valid for character").into_response();
+        }
+
+        let character = self.characters.get_mut(&character_id).unwrap();
+        if character.failures > 0 {
+            character.failures -= 1;
+            return (StatusCode::FORBIDDEN, "scripted failure").into_response();
+        }
+
+        let body = body(character).to_string();
+        let mut hasher = DefaultHasher::new();
+        body.hash(&mut hasher);
+        let etag = format!("\"{:x}\"", hasher.finish());
+        let expires = (chrono::Utc::now() + chrono::TimeDelta::seconds(120)).to_rfc2822();
+
+        let if_none_match = headers
+            .get("If-None-Match")
+            .and_then(|value| value.to_str().ok());
+        if if_none_match == Some(etag.as_str()) {
+            return (
+                StatusCode::NOT_MODIFIED,
+                [("ETag", etag), ("Expires", expires)],
+            )
+                .into_response();
+        }
+
+        (
+            StatusCode::OK,
+            [
+                ("ETag", etag),
+                ("Expires", expires),
+                ("Content-Type", "application/json".to_string()),
+            ],
+            body,
+        )
+            .into_response()
+    }
+}
+
+type MockShared = Arc<Mutex<MockState>>;
+
+async fn metadata(State(state): State<MockShared>) -> Json<Value> {
+    let state = state.lock().unwrap();
+    Json(json!({ "jwks_uri": format!("{}/oauth/jwks", state.base_url) }))
+}
+
+async fn jwks(State(state): State<MockShared>) -> Json<Value> {
+    Json(state.lock().unwrap().jwks.clone())
+}
+
+async fn token(
+    State(state): State<MockShared>,
+    Form(form): Form<HashMap<String, String>>,
+) -> Response {
+    let mut state = state.lock().unwrap();
+    state.requests.push("token".to_string());
+
+    let character_id = match form.get("grant_type").map(|s| s.as_str()) {
+        Some("authorization_code") => state.codes.remove(&form["code"]),
+        Some("refresh_token") => state.refresh_tokens.remove(&form["refresh_token"]),
+        _ => None,
+    };
+
+    match character_id {
+        Some(character_id) => Json(state.issue_tokens(character_id)).into_response(),
+        None => (
+            StatusCode::BAD_REQUEST,
+            Json(json!({ "error": "invalid_grant" })),
+        )
+            .into_response(),
+    }
+}
+
+async fn revoke(
+    State(state): State<MockShared>,
+    Form(form): Form<HashMap<String, String>>,
+) -> StatusCode {
+    let mut state = state.lock().unwrap();
+    state.requests.push("revoke".to_string());
+
+    if let Some(token) = form.get("token") {
+        state.refresh_tokens.remove(token);
+    }
+    StatusCode::OK
+}
+
+async fn skills(
+    State(state): State<MockShared>,
+    Path(character_id): Path<u64>,
+    headers: HeaderMap,
+) -> Response {
+    let mut state = state.lock().unwrap();
+    state.requests.push(format!("skills/{}", character_id));
+
+    state.character_body(&headers, character_id, |c| c.skills.clone())
+}
+
+async fn skill_queue(
+    State(state): State<MockShared>,
+    Path(character_id): Path<u64>,
+    headers: HeaderMap,
+) -> Response {
+    let mut state = state.lock().unwrap();
+    state.requests.push(format!("skillqueue/{}", character_id));
+
+    state.character_body(&headers, character_id, |c| c.skill_queue.clone())
+}
+
+async fn names(State(state): State<MockShared>, Json(ids): Json<Vec<i32>>) -> Response {
+    let mut state = state.lock().unwrap();
+    state.requests.push("names".to_string());
+
+    /* Like ESI, a single unknown ID fails the whole request. */
+    let mut result = Vec::new();
+    for id in ids {
+        match state.names.get(&id) {
+            Some(name) => {
+                result.push(json!({ "id": id, "category": "inventory_type", "name": name }))
+            }
+            None => return (StatusCode::NOT_FOUND, "unknown ID").into_response(),
+        }
+    }
+
+    Json(Value::Array(result)).into_response()
+}
+
+/* Scripted server errors, and the error limit headers ESI adds to every response. */
+async fn esi_limits(State(state): State<MockShared>, request: Request, next: Next) -> Response {
+    let scripted = {
+        let mut state = state.lock().unwrap();
+        match &mut state.server_errors {
+            Some((status, count)) if *count > 0 => {
+                *count -= 1;
+                let status = *status;
+                state.requests.push(format!("error/{}", status.as_u16()));
+                Some(status)
+            }
+            _ => None,
+        }
+    };
+
+    let mut response = match scripted {
+        Some(status) => (status, "scripted server error").into_response(),
+        None => next.run(request).await,
+    };
+
+    if let Some((remain, reset)) = state.lock().unwrap().error_limit {
+        let headers = response.headers_mut();
+        headers.insert("X-ESI-Error-Limit-Remain", remain.into());
+        headers.insert("X-ESI-Error-Limit-Reset", reset.into());
+    }
+    response
+}
+
+impl MockEsi {
+    pub async fn start() -> Self {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let base_url = format!("http://{}", listener.local_addr().unwrap());
+
+        let rsa = openssl::rsa::Rsa::generate(2048).unwrap();
+        let encoding_key = EncodingKey::from_rsa_pem(&rsa.private_key_to_pem().unwrap()).unwrap();
+        let jwk_set = json!({
+            "keys": [{
+                "kty": "RSA",
+                "alg": "RS256",
+                "use": "sig",
+                "kid": "mock",
+                "n": base64_url(&rsa.n().to_vec()),
+                "e": base64_url(&rsa.e().to_vec()),
+            }]
+        });
+
+        let state = Arc::new(Mutex::new(MockState {
+            base_url: base_url.clone(),
+            encoding_key,
+            jwks: jwk_set,
+            characters: HashMap::new(),
+            codes: HashMap::new(),
+            access_tokens: HashMap::new(),
+            refresh_tokens: HashMap::new(),
+            names: HashMap::new(),
+            requests: Vec::new(),
+            counter: 0,
+            error_limit: None,
+            server_errors: None,
+        }));
+
+        let app = Router::new()
+            .route("/.well-known/oauth-authorization-server", get(metadata))
+            .route("/oauth/jwks", get(jwks))
+            .route("/v2/oauth/token", post(token))
+            .route("/v2/oauth/revoke", post(revoke))
+            .route("/v4/characters/:character_id/skills/", get(skills))
+            .route("/v2/characters/:character_id/skillqueue/", get(skill_queue))
+            .route("/v3/universe/names/", post(names))
+            .layer(middleware::from_fn_with_state(state.clone(), esi_limits))
+            .with_state(state.clone());
+        tokio::spawn(async move {
+            axum::serve(listener, app).await.unwrap();
+        });
+
+        MockEsi { base_url, state }
+    }
+
+    /// Configuration to point an `Esi` instance to this mock.
+    pub fn esi_config(&self) -> EsiConfig {
+        EsiConfig {
+            client_id: CLIENT_ID.to_string(),
+            client_secret: "mock-client-secret".to_string(),
+            webserver_url: "http://localhost:3000".to_string(),
+            esi_base_url: self.base_url.clone(),
+            sso_base_url: self.base_url.clone(),
+            jwks_url: None,
+            storage_folder: None,
+            sde_folder: None,
+        }
+    }
+
+    /// Add a character that grants all required scopes, with no skills.
+    pub fn add_character(&self, character_id: u64, name: &str) {
+        let mut state = self.state.lock().unwrap();
+        state.characters.insert(
+            character_id,
+            MockCharacter {
+                name: name.to_string(),
+                scopes: REQUIRED_SCOPES.iter().map(|s| s.to_string()).collect(),
+                skills: json!({ "skills": [], "total_sp": 0, "unallocated_sp": 0 }),
+                skill_queue: json!([]),
+                failures: 0,
+            },
+        );
+    }
+
+    pub fn set_scopes(&self, character_id: u64, scopes: &[&str]) {
+        let mut state = self.state.lock().unwrap();
+        state.characters.get_mut(&character_id).unwrap().scopes =
+            scopes.iter().map(|s| s.to_string()).collect();
+    }
+
+    pub fn set_skills(&self, character_id: u64, skills: Value) {
+        let mut state = self.state.lock().unwrap();
+        state.characters.get_mut(&character_id).unwrap().skills = skills;
+    }
+
+    pub fn set_skill_queue(&self, character_id: u64, skill_queue: Value) {
+        let mut state = self.state.lock().unwrap();
+        state.characters.get_mut(&character_id).unwrap().skill_queue = skill_queue;
+    }
+
+    /// Answer the next `count` skill and skill queue requests with an error.
+    pub fn fail_next(&self, character_id: u64, count: u32) {
+        let mut state = self.state.lock().unwrap();
+        state.characters.get_mut(&character_id).unwrap().failures = count;
+    }
+
+    pub fn set_name(&self, id: i32, name: &str) {
+        let mut state = self.state.lock().unwrap();
+        state.names.insert(id, name.to_string());
+    }
+
+    /// Authorization code as SSO would hand to the callback after a successful login.
+    pub fn authorize(&self, character_id: u64) -> String {
+        let mut state = self.state.lock().unwrap();
+        state.counter += 1;
+
+        let code = format!("code-{}-{}", character_id, state.counter);
+        state.codes.insert(code.clone(), character_id);
+        code
+    }
+
+    /// Accept a refresh token that was handed out elsewhere, for example one in a storage fixture.
+    pub fn add_refresh_token(&self, character_id: u64, refresh_token: &str) {
+        let mut state = self.state.lock().unwrap();
+        state
+            .refresh_tokens
+            .insert(refresh_token.to_string(), character_id);
+    }
+
+    /// Invalidate all refresh tokens of a character, as if the user revoked access.
+    pub fn revoke_character(&self, character_id: u64) {
+        let mut state = self.state.lock().unwrap();
+        state.refresh_tokens.retain(|_, c| *c != character_id);
+    }
+
+    /// Report this error budget on every response from now on; None to stop reporting it.
+    pub fn set_error_limit(&self, error_limit: Option<(i64, u64)>) {
+        self.state.lock().unwrap().error_limit = error_limit;
+    }
+
+    /// Answer the next `count` requests, to any endpoint, with `status`.
+    pub fn fail_server(&self, status: u16, count: u32) {
+        self.state.lock().unwrap().server_errors =
+            Some((StatusCode::from_u16(status).unwrap(), count));
+    }
+
+    /// The claims of an access token for the character, as SSO would issue them.
+    pub fn access_token_claims(&self, character_id: u64) -> Value {
+        self.state.lock().unwrap().claims(character_id)
+    }
+
+    /// Sign a token with the key the mock publishes, for example after changing its claims.
+    pub fn sign_token(&self, claims: &Value, kid: Option<&str>) -> String {
+        self.state.lock().unwrap().sign(claims, kid)
+    }
+
+    /// All requests made so far, as short descriptions like `skills/<id>` or `names`.
+    pub fn requests(&self) -> Vec<String> {
+        self.state.lock().unwrap().requests.clone()
+    }
+}
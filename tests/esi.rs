@@ -0,0 +1,197 @@
+mod common;
+
+use common::mock_esi::MockEsi;
+use eve_skills_discord::esi::Esi;
+use jsonwebtoken::{encode, Algorithm, EncodingKey, Header};
+use serde_json::json;
+
+const CHARACTER_ID: u64 = 90000001;
+
+#[tokio::test]
+async fn test_esi_against_mock() {
+    let mock = MockEsi::start().await;
+    mock.add_character(CHARACTER_ID, "Mock Pilot");
+    mock.set_skills(
+        CHARACTER_ID,
+        json!({
+            "skills": [
+                { "skill_id": 3300, "skillpoints_in_skill": 256000, "trained_skill_level": 5, "active_skill_level": 5 },
+                { "skill_id": 3301, "skillpoints_in_skill": 8000, "trained_skill_level": 2, "active_skill_level": 2 },
+            ],
+            "total_sp": 264000,
+            "unallocated_sp": 1000,
+        }),
+    );
+    mock.set_name(3300, "Gunnery");
+    mock.set_name(3301, "Small Hybrid Turret");
+
+    let esi = Esi::new(mock.esi_config());
+
+    let (access_token, refresh_token) = esi
+        .exchange_code(mock.authorize(CHARACTER_ID))
+        .await
+        .unwrap();
+
+    let claims = esi.verify_jwt(&access_token).await.unwrap();
+    assert_eq!(claims.sub, format!("CHARACTER:EVE:{}", CHARACTER_ID));
+    assert_eq!(claims.name, "Mock Pilot");
+    assert_eq!(claims.scp.len(), 2);
+
+    let skills = esi.get_skills(&access_token, CHARACTER_ID).await.unwrap();
+    assert!(skills.modified);
+    assert!(skills.expires.is_some());
+    assert_eq!(skills.data.total_sp, 264000);
+    assert_eq!(skills.data.skills.len(), 2);
+
+    /* The second request is answered with "304 Not Modified", but returns the same data. */
+    let skills = esi.get_skills(&access_token, CHARACTER_ID).await.unwrap();
+    assert!(!skills.modified);
+    assert_eq!(skills.data.skills.len(), 2);
+
+    let skill_queue = esi
+        .get_skill_queue(&access_token, CHARACTER_ID)
+        .await
+        .unwrap();
+    assert!(skill_queue.data.0.is_empty());
+
+    /* All names are looked up in a single request, and only once. */
+    let names = esi.lookup_skill_names(&[3300, 3301]).await.unwrap();
+    assert_eq!(names[&3300], "Gunnery");
+    assert_eq!(names[&3301], "Small Hybrid Turret");
+    esi.lookup_skill_names(&[3301]).await.unwrap();
+    let name_requests = mock.requests().iter().filter(|r| *r == "names").count();
+    assert_eq!(name_requests, 1);
+
+    /* ESI rejects the whole batch for a single invalid ID; the others still resolve. */
+    mock.set_name(3302, "Medium Hybrid Turret");
+    let names = esi.lookup_skill_names(&[3302, 99999]).await.unwrap();
+    assert_eq!(names[&3302], "Medium Hybrid Turret");
+    assert!(!names.contains_key(&99999));
+
+    let (access_token, refresh_token) = esi.exchange_refresh_token(refresh_token).await.unwrap();
+    assert!(esi.verify_jwt(&access_token).await.is_ok());
+
+    esi.revoke_refresh_token(refresh_token.clone())
+        .await
+        .unwrap();
+    assert!(esi.exchange_refresh_token(refresh_token).await.is_err());
+
+    /* Tokens of other characters are rejected. */
+    mock.add_character(CHARACTER_ID + 1, "Other Pilot");
+    assert!(esi
+        .get_skills(&access_token, CHARACTER_ID + 1)
+        .await
+        .is_err());
+}
+
+#[tokio::test]
+async fn test_skill_names_from_sde() {
+    let mock = MockEsi::start().await;
+    let esi = Esi::new(eve_skills_discord::esi::EsiConfig {
+        sde_folder: Some("tests/fixtures/sde".to_string()),
+        ..mock.esi_config()
+    });
+
+    /* Only skills are taken from the SDE; they are known without asking ESI. */
+    assert_eq!(
+        esi.cached_skill_name(3300).await.as_deref(),
+        Some("Gunnery")
+    );
+    assert_eq!(
+        esi.cached_skill_name(3301).await.as_deref(),
+        Some("Small Hybrid Turret")
+    );
+    assert_eq!(esi.cached_skill_name(561).await, None);
+    let names = esi.lookup_skill_names(&[3300, 3301]).await.unwrap();
+    assert_eq!(names.len(), 2);
+    assert!(mock.requests().iter().all(|r| r != "names"));
+}
+
+#[tokio::test]
+async fn test_verify_jwt_rejects_invalid_tokens() {
+    let mock = MockEsi::start().await;
+    mock.add_character(CHARACTER_ID, "Mock Pilot");
+    let esi = Esi::new(mock.esi_config());
+
+    let claims = mock.access_token_claims(CHARACTER_ID);
+    assert!(esi
+        .verify_jwt(&mock.sign_token(&claims, Some("mock")))
+        .await
+        .is_ok());
+
+    /* Signed with a key that is not in the key set, under a known and an unknown key ID. */
+    let rsa = openssl::rsa::Rsa::generate(2048).unwrap();
+    let other_key = EncodingKey::from_rsa_pem(&rsa.private_key_to_pem().unwrap()).unwrap();
+    for kid in ["mock", "other"] {
+        let mut header = Header::new(Algorithm::RS256);
+        header.kid = Some(kid.to_string());
+        let token = encode(&header, &claims, &other_key).unwrap();
+        assert!(esi.verify_jwt(&token).await.is_err());
+    }
+
+    /* Issued for another application. */
+    let mut wrong_audience = claims.clone();
+    wrong_audience["aud"] = json!(["other-client-id", "EVE Online"]);
+    assert!(esi
+        .verify_jwt(&mock.sign_token(&wrong_audience, Some("mock")))
+        .await
+        .is_err());
+
+    /* Issued by someone else. */
+    let mut wrong_issuer = claims.clone();
+    wrong_issuer["iss"] = json!("https://login.example.com");
+    assert!(esi
+        .verify_jwt(&mock.sign_token(&wrong_issuer, Some("mock")))
+        .await
+        .is_err());
+
+    let mut expired = claims.clone();
+    expired["exp"] = json!(chrono::Utc::now().timestamp() - 3600);
+    assert!(esi
+        .verify_jwt(&mock.sign_token(&expired, Some("mock")))
+        .await
+        .is_err());
+
+    assert!(esi
+        .verify_jwt(&mock.sign_token(&claims, None))
+        .await
+        .is_err());
+}
+
+#[tokio::test]
+async fn test_error_limit_pauses_requests() {
+    let mock = MockEsi::start().await;
+    mock.set_name(3300, "Gunnery");
+    mock.set_name(3301, "Small Hybrid Turret");
+    let esi = Esi::new(mock.esi_config());
+
+    /* Only 10 errors left: all traffic waits till the window resets. */
+    mock.set_error_limit(Some((10, 2)));
+    esi.lookup_skill_names(&[3300]).await.unwrap();
+    mock.set_error_limit(None);
+
+    let start = std::time::Instant::now();
+    esi.lookup_skill_names(&[3301]).await.unwrap();
+    assert!(start.elapsed() >= std::time::Duration::from_millis(1500));
+}
+
+#[tokio::test]
+async fn test_server_errors_are_retried() {
+    let mock = MockEsi::start().await;
+    mock.set_name(3300, "Gunnery");
+    mock.set_name(3301, "Small Hybrid Turret");
+    let esi = Esi::new(mock.esi_config());
+    let server_errors = || mock.requests().iter().filter(|r| *r == "error/502").count();
+
+    /* A single 502 is retried. */
+    mock.fail_server(502, 1);
+    let names = esi.lookup_skill_names(&[3300]).await.unwrap();
+    assert_eq!(names[&3300], "Gunnery");
+    assert_eq!(server_errors(), 1);
+
+    /* After the first attempt and three retries, it gives up. */
+    mock.fail_server(502, 10);
+    assert!(esi.lookup_skill_names(&[3301]).await.is_err());
+    assert_eq!(server_errors(), 1 + 4);
+    assert_eq!(mock.requests().iter().filter(|r| *r == "names").count(), 1);
+}
@@ -0,0 +1,929 @@
+mod common;
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use common::mock_esi::MockEsi;
+use eve_skills_discord::esi::Esi;
+use eve_skills_discord::monitor::{
+    parse_thresholds, ChannelAction, FinishedSkill, Monitor, MonitorConfig, ReportPeriod, SpSample,
+    StorageConfig, StorageKey, StorageKind, TrainingSpan,
+};
+use eve_skills_discord::notifier::{Notifier, RecordingNotifier};
+use eve_skills_discord::state::BotState;
+use eve_skills_discord::webserver::create_app;
+use serde_json::{json, Value};
+
+const CHARACTER_ID: u64 = 90000001;
+const DISCORD_GUILD_ID: u64 = 1000;
+const DISCORD_USER_ID: u64 = 2000;
+const DISCORD_CATEGORY_ID: u64 = 3000;
+
+struct Harness {
+    mock: MockEsi,
+    bot: BotState,
+    monitor: Arc<Monitor>,
+    recorder: Arc<RecordingNotifier>,
+    storage_folder: std::path::PathBuf,
+    app_url: String,
+    http: reqwest::Client,
+}
+
+impl Harness {
+    async fn start(name: &str, storage_kind: StorageKind) -> Self {
+        let mock = MockEsi::start().await;
+
+        let storage_folder = std::env::temp_dir().join(format!(
+            "eve-skills-discord-{}-{}",
+            name,
+            std::process::id()
+        ));
+        let _ = std::fs::remove_dir_all(&storage_folder);
+        std::fs::create_dir_all(&storage_folder).unwrap();
+
+        let bot = BotState::new("Timed out".to_string(), Esi::new(mock.esi_config()));
+        let recorder = Arc::new(RecordingNotifier::new());
+        bot.set_notifier(recorder.clone()).await;
+
+        /* The scheduler is not started; the tests refresh characters explicitly. */
+        let monitor = Arc::new(Monitor::new(
+            bot.clone(),
+            MonitorConfig {
+                storage: StorageConfig {
+                    folder: storage_folder.to_str().unwrap().to_string(),
+                    kind: storage_kind,
+                    key: StorageKey::parse(&StorageKey::generate()).unwrap(),
+                },
+                discord_category_id: DISCORD_CATEGORY_ID,
+                concurrency: 1,
+                report_period: ReportPeriod::Daily,
+                queue_warnings: parse_thresholds("24h,1h").unwrap(),
+            },
+        ));
+        bot.set_monitor(monitor.clone()).await;
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let app_url = format!("http://{}", listener.local_addr().unwrap());
+        let app = create_app(bot.clone());
+        tokio::spawn(async move {
+            axum::serve(listener, app).await.unwrap();
+        });
+
+        let http = reqwest::Client::builder()
+            .redirect(reqwest::redirect::Policy::none())
+            .build()
+            .unwrap();
+
+        Harness {
+            mock,
+            bot,
+            monitor,
+            recorder,
+            storage_folder,
+            app_url,
+            http,
+        }
+    }
+
+    /// Go through /monitor, /login and /callback, and return the final response on Discord.
+    async fn authenticate(&self, character_id: u64, state: &str) -> String {
+        let token = format!("interaction-{}", state);
+        self.bot
+            .pending_create(
+                state.to_string(),
+                DISCORD_GUILD_ID,
+                DISCORD_USER_ID,
+                token.clone(),
+            )
+            .await;
+
+        let response = self
+            .http
+            .get(format!("{}/login?state={}", self.app_url, state))
+            .send()
+            .await
+            .unwrap();
+        assert!(response.status().is_redirection());
+        let location = response.headers()["Location"].to_str().unwrap();
+        assert!(location.starts_with(&self.mock.base_url));
+        assert!(location.contains(&format!("state={}", state)));
+
+        let response = self
+            .http
+            .get(format!(
+                "{}/callback?code={}&state={}",
+                self.app_url,
+                self.mock.authorize(character_id),
+                state
+            ))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), 200);
+
+        /* The install itself happens in the background; wait for it to report back. */
+        wait_for(|| async { !self.bot.pending_exists(state).await }).await;
+
+        self.recorder.response(&token).unwrap()
+    }
+}
+
+async fn wait_for<F, Fut>(condition: F)
+where
+    F: Fn() -> Fut,
+    Fut: std::future::Future<Output = bool>,
+{
+    for _ in 0..100 {
+        if condition().await {
+            return;
+        }
+        tokio::time::sleep(Duration::from_millis(20)).await;
+    }
+    panic!("Condition not met in time");
+}
+
+fn skill(skill_id: i32, level: i32, skillpoints: i64) -> Value {
+    json!({
+        "skill_id": skill_id,
+        "skillpoints_in_skill": skillpoints,
+        "trained_skill_level": level,
+        "active_skill_level": level,
+    })
+}
+
+fn queue_item(
+    skill_id: i32,
+    finished_level: i32,
+    start_date: chrono::DateTime<chrono::Utc>,
+    finish_date: chrono::DateTime<chrono::Utc>,
+) -> Value {
+    json!({
+        "skill_id": skill_id,
+        "finished_level": finished_level,
+        "queue_position": 0,
+        "level_start_sp": 1415,
+        "level_end_sp": 8000,
+        "training_start_sp": 1415,
+        "start_date": start_date.to_rfc3339(),
+        "finish_date": finish_date.to_rfc3339(),
+    })
+}
+
+fn skills(skills: Vec<Value>) -> Value {
+    let total_sp: i64 = skills
+        .iter()
+        .map(|s| s["skillpoints_in_skill"].as_i64().unwrap())
+        .sum();
+    json!({ "skills": skills, "total_sp": total_sp, "unallocated_sp": 0 })
+}
+
+async fn monitor_lifecycle(name: &str, storage_kind: StorageKind) {
+    let harness = Harness::start(name, storage_kind).await;
+    let mock = &harness.mock;
+    let recorder = &harness.recorder;
+
+    mock.add_character(CHARACTER_ID, "Mock Pilot");
+    mock.set_name(3300, "Gunnery");
+    mock.set_name(3301, "Small Hybrid Turret");
+    mock.set_name(3302, "Medium Hybrid Turret");
+    mock.set_skills(
+        CHARACTER_ID,
+        skills(vec![skill(3300, 3, 40000), skill(3301, 2, 1415)]),
+    );
+    let finish = chrono::Utc::now() + chrono::TimeDelta::hours(48);
+    mock.set_skill_queue(
+        CHARACTER_ID,
+        json!([queue_item(3301, 3, chrono::Utc::now(), finish)]),
+    );
+
+    /* Install: a private channel with an activity thread is created. */
+    let response = harness.authenticate(CHARACTER_ID, "first").await;
+    let (channel_id, thread_id) = recorder
+        .notifications()
+        .into_iter()
+        .find_map(|n| match n {
+            eve_skills_discord::notifier::Notification::CreateChannel {
+                guild_id,
+                character_id,
+                name,
+                channel_id,
+                thread_id,
+            } => {
+                assert_eq!(guild_id, DISCORD_GUILD_ID);
+                assert_eq!(character_id, DISCORD_USER_ID);
+                assert_eq!(name, "Mock Pilot");
+                Some((channel_id, thread_id))
+            }
+            _ => None,
+        })
+        .expect("No channel created");
+    assert_eq!(
+        response,
+        format!("Your character is now monitored in <#{}>", channel_id)
+    );
+    assert_eq!(recorder.messages(channel_id), vec!["Update pending ..."]);
+
+    /* First load: the queue is shown, but no changes are reported. */
+    assert!(harness.monitor.refresh_now(CHARACTER_ID).await);
+    assert_eq!(
+        recorder.messages(channel_id),
+        vec![format!(
+            "`Small Hybrid Turret III` is training; skill queue will finish <t:{}:R>.",
+            finish.timestamp()
+        )]
+    );
+    let embed = recorder.embed(channel_id).expect("No status embed");
+    assert_eq!(embed.title, "Mock Pilot");
+    assert_eq!(
+        embed.thumbnail.as_deref(),
+        Some(
+            format!(
+                "https://images.evetech.net/characters/{}/portrait",
+                CHARACTER_ID
+            )
+            .as_str()
+        )
+    );
+    assert_eq!(embed.colour, 0x2ecc71);
+    assert_eq!(embed.footer, "Total SP: 41,415");
+    assert!(embed.description.starts_with(&format!(
+        "Skill queue will finish <t:{}:R>",
+        finish.timestamp()
+    )));
+    assert_eq!(embed.fields.len(), 1);
+    assert_eq!(embed.fields[0].name, "Small Hybrid Turret III");
+    assert_eq!(
+        embed.fields[0].value,
+        format!(
+            "Finishes <t:{}:R>.\n0% done, training at 137 SP/hour.",
+            finish.timestamp()
+        )
+    );
+    assert!(recorder.messages(thread_id).is_empty());
+
+    /* Skill completion: the queue entry finished, before ESI updated the skills. */
+    let finished = chrono::Utc::now() - chrono::TimeDelta::minutes(5);
+    mock.set_skill_queue(
+        CHARACTER_ID,
+        json!([queue_item(
+            3301,
+            3,
+            finished - chrono::TimeDelta::hours(1),
+            finished
+        )]),
+    );
+    assert!(harness.monitor.refresh_now(CHARACTER_ID).await);
+    assert_eq!(
+        recorder.messages(thread_id),
+        vec!["`Small Hybrid Turret III` has finished training.\n"]
+    );
+    assert_eq!(recorder.messages(channel_id), vec!["Skill queue empty."]);
+    let embed = recorder.embed(channel_id).expect("No status embed");
+    assert_eq!(embed.colour, 0xe74c3c);
+    assert!(embed.fields.is_empty());
+
+    /* Injection: a new skill shows up at level 0. */
+    mock.set_skill_queue(CHARACTER_ID, json!([]));
+    mock.set_skills(
+        CHARACTER_ID,
+        skills(vec![
+            skill(3300, 3, 40000),
+            skill(3301, 3, 8000),
+            skill(3302, 0, 0),
+        ]),
+    );
+    assert!(harness.monitor.refresh_now(CHARACTER_ID).await);
+    assert_eq!(
+        recorder.messages(thread_id).last().unwrap(),
+        "`Medium Hybrid Turret` has been injected.\n"
+    );
+
+    /* Expiry: after eight failed refreshes in a row, monitoring is suspended. */
+    mock.revoke_character(CHARACTER_ID);
+    for _ in 0..7 {
+        assert!(harness.monitor.refresh_now(CHARACTER_ID).await);
+    }
+    assert!(!harness.monitor.refresh_now(CHARACTER_ID).await);
+    assert!(recorder
+        .messages(thread_id)
+        .last()
+        .unwrap()
+        .contains("Monitoring suspended."));
+    assert!(
+        harness
+            .monitor
+            .read_from_storage(CHARACTER_ID)
+            .unwrap()
+            .expired
+    );
+    assert!(!harness.monitor.has_eve_character(CHARACTER_ID).await);
+
+    /* Re-authentication reuses the existing channel, and monitoring resumes. */
+    let notifications = recorder.notifications().len();
+    let response = harness.authenticate(CHARACTER_ID, "second").await;
+    assert_eq!(
+        response,
+        format!("Your character is now monitored in <#{}>", channel_id)
+    );
+    assert!(!recorder.notifications()[notifications..]
+        .iter()
+        .any(|n| matches!(
+            n,
+            eve_skills_discord::notifier::Notification::CreateChannel { .. }
+        )));
+    assert!(
+        !harness
+            .monitor
+            .read_from_storage(CHARACTER_ID)
+            .unwrap()
+            .expired
+    );
+
+    mock.set_skills(
+        CHARACTER_ID,
+        skills(vec![
+            skill(3300, 4, 135765),
+            skill(3301, 3, 8000),
+            skill(3302, 0, 0),
+        ]),
+    );
+    assert!(harness.monitor.refresh_now(CHARACTER_ID).await);
+    assert_eq!(
+        recorder.messages(thread_id).last().unwrap(),
+        "`Gunnery IV` has finished training.\n"
+    );
+}
+
+#[tokio::test]
+async fn test_monitor_lifecycle_json() {
+    monitor_lifecycle("lifecycle-json", StorageKind::Json).await;
+}
+
+#[tokio::test]
+async fn test_monitor_lifecycle_sqlite() {
+    monitor_lifecycle("lifecycle-sqlite", StorageKind::Sqlite).await;
+}
+
+#[tokio::test]
+async fn test_install_with_missing_scopes() {
+    let harness = Harness::start("scopes", StorageKind::Json).await;
+
+    harness.mock.add_character(CHARACTER_ID, "Mock Pilot");
+    harness
+        .mock
+        .set_scopes(CHARACTER_ID, &["esi-skills.read_skills.v1"]);
+
+    let response = harness.authenticate(CHARACTER_ID, "scopes").await;
+    assert!(response.starts_with("Failed to monitor character: Not all permissions were granted"));
+    assert!(harness.recorder.notifications().iter().all(|n| matches!(
+        n,
+        eve_skills_discord::notifier::Notification::EditInteractionResponse { .. }
+    )));
+    assert!(harness.monitor.read_from_storage(CHARACTER_ID).is_err());
+}
+
+#[tokio::test]
+async fn test_daily_sp_report() {
+    let harness = Harness::start("report", StorageKind::Json).await;
+    let mock = &harness.mock;
+
+    mock.add_character(CHARACTER_ID, "Mock Pilot");
+    mock.set_name(3300, "Gunnery");
+    mock.set_skills(CHARACTER_ID, skills(vec![skill(3300, 4, 135765)]));
+    harness.authenticate(CHARACTER_ID, "report").await;
+
+    /* The first refresh only starts the history. */
+    assert!(harness.monitor.refresh_now(CHARACTER_ID).await);
+    let storage = harness.monitor.read_from_storage(CHARACTER_ID).unwrap();
+    assert_eq!(storage.history.samples.len(), 1);
+    assert_eq!(storage.history.samples[0].total_sp, 135765);
+    assert!(storage.history.last_report.is_some());
+    let thread_id = storage.discord_activity_thread_id;
+    assert!(harness.recorder.messages(thread_id).is_empty());
+
+    /* Pretend the character was monitored all of yesterday, finishing Gunnery IV at noon, pausing
+     * the queue for six hours, and training at a lower rate after. */
+    let today = chrono::Utc::now()
+        .date_naive()
+        .and_hms_opt(0, 0, 0)
+        .unwrap()
+        .and_utc();
+    let yesterday = today - chrono::TimeDelta::days(1);
+    let mut storage = storage;
+    storage.history.samples = vec![
+        SpSample {
+            time: yesterday - chrono::TimeDelta::hours(1),
+            total_sp: 111765,
+            unallocated_sp: 0,
+        },
+        SpSample {
+            time: yesterday + chrono::TimeDelta::hours(12),
+            total_sp: 135765,
+            unallocated_sp: 0,
+        },
+    ];
+    storage.history.training = vec![
+        TrainingSpan {
+            start: yesterday - chrono::TimeDelta::hours(1),
+            end: yesterday + chrono::TimeDelta::hours(12),
+            sp_per_hour: 1800.0,
+        },
+        TrainingSpan {
+            start: yesterday + chrono::TimeDelta::hours(18),
+            end: today,
+            sp_per_hour: 400.0,
+        },
+    ];
+    storage.history.finished = vec![FinishedSkill {
+        time: yesterday + chrono::TimeDelta::hours(12),
+        skill_id: 3300,
+        level: 4,
+    }];
+    storage.history.last_report = Some(yesterday - chrono::TimeDelta::hours(1));
+    harness.monitor.write_to_storage(CHARACTER_ID, storage);
+
+    assert!(harness.monitor.refresh_now(CHARACTER_ID).await);
+    assert_eq!(
+        harness.recorder.messages(thread_id),
+        vec![format!(
+            "**Daily summary** (<t:{}:d> - <t:{}:d>)\n- 24,000 SP trained, 1,000 SP/hour.\n- 1 skill finished: `Gunnery IV`.\n",
+            yesterday.timestamp(),
+            today.timestamp() - 1,
+        )]
+    );
+
+    /* Only once per day. */
+    assert!(harness.monitor.refresh_now(CHARACTER_ID).await);
+    assert_eq!(harness.recorder.messages(thread_id).len(), 1);
+}
+
+#[tokio::test]
+async fn test_training_recorded_from_queue() {
+    let harness = Harness::start("training", StorageKind::Json).await;
+    let mock = &harness.mock;
+
+    let now = chrono::Utc::now();
+    let finish = now + chrono::TimeDelta::hours(48);
+    mock.add_character(CHARACTER_ID, "Mock Pilot");
+    mock.set_skills(CHARACTER_ID, skills(vec![skill(3301, 2, 1415)]));
+    mock.set_skill_queue(CHARACTER_ID, json!([queue_item(3301, 3, now, finish)]));
+    harness.authenticate(CHARACTER_ID, "training").await;
+
+    /* Refreshing while the same skill trains doesn't split it up. */
+    assert!(harness.monitor.refresh_now(CHARACTER_ID).await);
+    assert!(harness.monitor.refresh_now(CHARACTER_ID).await);
+    let training = harness
+        .monitor
+        .read_from_storage(CHARACTER_ID)
+        .unwrap()
+        .history
+        .training;
+    assert_eq!(training.len(), 1);
+    assert_eq!(training[0].end, finish);
+    assert_eq!(training[0].sp_per_hour.round(), 137.0);
+
+    /* Pausing the queue ends the training. */
+    mock.set_skill_queue(
+        CHARACTER_ID,
+        json!([{
+            "skill_id": 3301,
+            "finished_level": 3,
+            "queue_position": 0,
+            "level_start_sp": 1415,
+            "level_end_sp": 8000,
+            "training_start_sp": 1415,
+        }]),
+    );
+    assert!(harness.monitor.refresh_now(CHARACTER_ID).await);
+    let training = harness
+        .monitor
+        .read_from_storage(CHARACTER_ID)
+        .unwrap()
+        .history
+        .training;
+    assert_eq!(training.len(), 1);
+    assert!(training[0].end < finish);
+}
+
+#[tokio::test]
+async fn test_queue_warnings() {
+    let harness = Harness::start("warnings", StorageKind::Json).await;
+    let mock = &harness.mock;
+
+    mock.add_character(CHARACTER_ID, "Mock Pilot");
+    mock.set_skills(CHARACTER_ID, skills(vec![skill(3301, 2, 1415)]));
+    let now = chrono::Utc::now();
+    let queue_until = |end| json!([queue_item(3301, 3, now, end)]);
+
+    /* Less than an hour left: both thresholds are crossed, but the user is warned once. */
+    let end = now + chrono::TimeDelta::minutes(30);
+    mock.set_skill_queue(CHARACTER_ID, queue_until(end));
+    harness.authenticate(CHARACTER_ID, "warnings").await;
+    assert!(harness.monitor.refresh_now(CHARACTER_ID).await);
+
+    let thread_id = harness
+        .monitor
+        .read_from_storage(CHARACTER_ID)
+        .unwrap()
+        .discord_activity_thread_id;
+    let warning = |end: chrono::DateTime<chrono::Utc>| {
+        format!(
+            "<@{}>: Skill queue will run empty <t:{}:R>.",
+            DISCORD_USER_ID,
+            end.timestamp()
+        )
+    };
+    assert_eq!(harness.recorder.messages(thread_id), vec![warning(end)]);
+
+    assert!(harness.monitor.refresh_now(CHARACTER_ID).await);
+    assert_eq!(harness.recorder.messages(thread_id).len(), 1);
+
+    /* Extending the queue resets the warnings. */
+    mock.set_skill_queue(
+        CHARACTER_ID,
+        queue_until(now + chrono::TimeDelta::hours(30)),
+    );
+    assert!(harness.monitor.refresh_now(CHARACTER_ID).await);
+    assert_eq!(harness.recorder.messages(thread_id).len(), 1);
+
+    let end = now + chrono::TimeDelta::hours(10);
+    mock.set_skill_queue(CHARACTER_ID, queue_until(end));
+    assert!(harness.monitor.refresh_now(CHARACTER_ID).await);
+    assert_eq!(harness.recorder.messages(thread_id)[1], warning(end));
+    assert_eq!(harness.recorder.messages(thread_id).len(), 2);
+}
+
+#[tokio::test]
+async fn test_queue_paused_and_resumed() {
+    let harness = Harness::start("paused", StorageKind::Json).await;
+    let mock = &harness.mock;
+
+    mock.add_character(CHARACTER_ID, "Mock Pilot");
+    mock.set_skills(CHARACTER_ID, skills(vec![skill(3301, 2, 1415)]));
+    let now = chrono::Utc::now();
+    let training = json!([queue_item(3301, 3, now, now + chrono::TimeDelta::hours(48))]);
+    let paused = json!([{
+        "skill_id": 3301,
+        "finished_level": 3,
+        "queue_position": 0,
+        "level_start_sp": 1415,
+        "level_end_sp": 8000,
+        "training_start_sp": 1415,
+    }]);
+
+    /* A queue that is paused from the start is no change. */
+    mock.set_skill_queue(CHARACTER_ID, paused.clone());
+    harness.authenticate(CHARACTER_ID, "paused").await;
+    assert!(harness.monitor.refresh_now(CHARACTER_ID).await);
+    let thread_id = harness
+        .monitor
+        .read_from_storage(CHARACTER_ID)
+        .unwrap()
+        .discord_activity_thread_id;
+    assert!(harness.recorder.messages(thread_id).is_empty());
+
+    mock.set_skill_queue(CHARACTER_ID, training.clone());
+    assert!(harness.monitor.refresh_now(CHARACTER_ID).await);
+    assert_eq!(
+        harness.recorder.messages(thread_id),
+        vec!["Skill queue resumed."]
+    );
+
+    mock.set_skill_queue(CHARACTER_ID, paused);
+    assert!(harness.monitor.refresh_now(CHARACTER_ID).await);
+    assert_eq!(
+        harness.recorder.messages(thread_id).last().unwrap(),
+        &format!("<@{}>: Skill queue paused.", DISCORD_USER_ID)
+    );
+
+    /* Nothing changes while it stays paused. */
+    assert!(harness.monitor.refresh_now(CHARACTER_ID).await);
+    assert_eq!(harness.recorder.messages(thread_id).len(), 2);
+}
+
+#[tokio::test]
+async fn test_unallocated_sp_changes() {
+    let harness = Harness::start("unallocated", StorageKind::Json).await;
+    let mock = &harness.mock;
+
+    mock.add_character(CHARACTER_ID, "Mock Pilot");
+    let with_unallocated = |skillpoints: i64, unallocated_sp: i64| {
+        let mut skills = skills(vec![skill(3300, 3, skillpoints)]);
+        skills["unallocated_sp"] = unallocated_sp.into();
+        skills
+    };
+
+    mock.set_skills(CHARACTER_ID, with_unallocated(40000, 100000));
+    harness.authenticate(CHARACTER_ID, "unallocated").await;
+    assert!(harness.monitor.refresh_now(CHARACTER_ID).await);
+    let thread_id = harness
+        .monitor
+        .read_from_storage(CHARACTER_ID)
+        .unwrap()
+        .discord_activity_thread_id;
+    assert!(harness.recorder.messages(thread_id).is_empty());
+
+    mock.set_skills(CHARACTER_ID, with_unallocated(40000, 600000));
+    assert!(harness.monitor.refresh_now(CHARACTER_ID).await);
+    assert_eq!(
+        harness.recorder.messages(thread_id).last().unwrap(),
+        "Unallocated SP went up by 500,000 (now 600,000); likely a skill injector or a reward.\n"
+    );
+
+    mock.set_skills(CHARACTER_ID, with_unallocated(240000, 400000));
+    assert!(harness.monitor.refresh_now(CHARACTER_ID).await);
+    assert_eq!(
+        harness.recorder.messages(thread_id).last().unwrap(),
+        "Unallocated SP went down by 200,000 (now 400,000); likely applied to skills.\n"
+    );
+}
+
+#[tokio::test]
+async fn test_status_message_tracked() {
+    let harness = Harness::start("status", StorageKind::Json).await;
+    let mock = &harness.mock;
+    let recorder = &harness.recorder;
+
+    mock.add_character(CHARACTER_ID, "Mock Pilot");
+    mock.set_skills(CHARACTER_ID, skills(vec![skill(3301, 2, 1415)]));
+    mock.set_skill_queue(CHARACTER_ID, json!([]));
+
+    harness.authenticate(CHARACTER_ID, "status").await;
+    let storage = harness.monitor.read_from_storage(CHARACTER_ID).unwrap();
+    let channel_id = storage.discord_channel_id;
+    let status_message_id = storage.discord_status_message_id.unwrap();
+    assert_eq!(recorder.pins(channel_id), vec![status_message_id]);
+
+    /* Someone else posting in the channel doesn't move the status. */
+    recorder.post_as_user(channel_id, "Hello");
+    assert!(harness.monitor.refresh_now(CHARACTER_ID).await);
+    assert_eq!(
+        recorder.messages(channel_id),
+        vec!["Skill queue empty.", "Hello"]
+    );
+
+    /* A deleted status message is posted (and pinned) again. */
+    recorder.delete_message(channel_id, status_message_id);
+    mock.set_skills(CHARACTER_ID, skills(vec![skill(3301, 3, 8000)]));
+    assert!(harness.monitor.refresh_now(CHARACTER_ID).await);
+    assert_eq!(
+        recorder.messages(channel_id),
+        vec!["Hello", "Skill queue empty."]
+    );
+    let new_status_message_id = harness
+        .monitor
+        .read_from_storage(CHARACTER_ID)
+        .unwrap()
+        .discord_status_message_id
+        .unwrap();
+    assert_ne!(new_status_message_id, status_message_id);
+    assert_eq!(recorder.pins(channel_id), vec![new_status_message_id]);
+    assert!(recorder.embed(channel_id).is_some());
+}
+
+#[tokio::test]
+async fn test_storage_read_failure_is_retried() {
+    let harness = Harness::start("read-failure", StorageKind::Json).await;
+    let mock = &harness.mock;
+
+    mock.add_character(CHARACTER_ID, "Mock Pilot");
+    mock.set_skills(CHARACTER_ID, skills(vec![skill(3301, 2, 1415)]));
+    mock.set_skill_queue(CHARACTER_ID, json!([]));
+    harness.authenticate(CHARACTER_ID, "read-failure").await;
+
+    /* The storage is unreadable for a while; the character stays monitored. A directory in its
+     * place fails the read, but not like a missing file would. */
+    let path = harness
+        .storage_folder
+        .join(format!("char-{}.json", CHARACTER_ID));
+    let moved = harness.storage_folder.join("moved.json");
+    std::fs::rename(&path, &moved).unwrap();
+    std::fs::create_dir(&path).unwrap();
+    assert!(harness.monitor.refresh_now(CHARACTER_ID).await);
+    assert!(harness.monitor.has_eve_character(CHARACTER_ID).await);
+
+    std::fs::remove_dir(&path).unwrap();
+    std::fs::rename(&moved, &path).unwrap();
+    assert!(harness.monitor.refresh_now(CHARACTER_ID).await);
+    assert_eq!(
+        harness
+            .monitor
+            .read_from_storage(CHARACTER_ID)
+            .unwrap()
+            .total_sp,
+        1415
+    );
+
+    /* A corrupt file is moved aside; the character is dropped, and can be installed again. */
+    std::fs::write(&path, "not json").unwrap();
+    assert!(!harness.monitor.refresh_now(CHARACTER_ID).await);
+    assert!(!harness.monitor.has_eve_character(CHARACTER_ID).await);
+
+    let response = harness
+        .authenticate(CHARACTER_ID, "read-failure-again")
+        .await;
+    assert!(response.starts_with("Your character is now monitored in"));
+    assert!(harness.monitor.has_eve_character(CHARACTER_ID).await);
+}
+
+#[tokio::test]
+async fn test_no_unallocated_sp_report_after_upgrade() {
+    let harness = Harness::start("upgrade-unallocated", StorageKind::Json).await;
+    let mock = &harness.mock;
+    let recorder = &harness.recorder;
+
+    /* A character stored before the total and unallocated SP were kept. */
+    let (channel_id, thread_id) = recorder
+        .create_private_channel(
+            DISCORD_GUILD_ID,
+            DISCORD_CATEGORY_ID,
+            DISCORD_USER_ID,
+            "Old Pilot",
+        )
+        .await
+        .unwrap();
+    let mut fixture: Value = serde_json::from_str(
+        &std::fs::read_to_string("tests/fixtures/storage/v1-minimal.json").unwrap(),
+    )
+    .unwrap();
+    fixture["discord_channel_id"] = channel_id.into();
+    fixture["discord_activity_thread_id"] = thread_id.into();
+    std::fs::write(
+        harness
+            .storage_folder
+            .join(format!("char-{}.json", CHARACTER_ID)),
+        fixture.to_string(),
+    )
+    .unwrap();
+
+    mock.add_character(CHARACTER_ID, "Old Pilot");
+    mock.add_refresh_token(CHARACTER_ID, "refresh-v1-minimal");
+    let mut skills = skills(vec![skill(3300, 3, 45255)]);
+    skills["total_sp"] = 95255.into();
+    skills["unallocated_sp"] = 50000.into();
+    mock.set_skills(CHARACTER_ID, skills);
+
+    harness.monitor.load_all_eve_characters().await;
+    assert!(harness.monitor.refresh_now(CHARACTER_ID).await);
+    assert!(recorder.messages(thread_id).is_empty());
+
+    let storage = harness.monitor.read_from_storage(CHARACTER_ID).unwrap();
+    assert_eq!(storage.total_sp, 95255);
+    assert_eq!(storage.unallocated_sp, 50000);
+}
+
+#[tokio::test]
+async fn test_status_message_adopted_after_upgrade() {
+    let harness = Harness::start("upgrade-status", StorageKind::Json).await;
+    let mock = &harness.mock;
+    let recorder = &harness.recorder;
+
+    /* A character stored before the status message was tracked; its status is the last message. */
+    let (channel_id, thread_id) = recorder
+        .create_private_channel(
+            DISCORD_GUILD_ID,
+            DISCORD_CATEGORY_ID,
+            DISCORD_USER_ID,
+            "Old Pilot",
+        )
+        .await
+        .unwrap();
+    let message_id = recorder
+        .send_message(channel_id, "Old status")
+        .await
+        .unwrap();
+    let mut fixture: Value = serde_json::from_str(
+        &std::fs::read_to_string("tests/fixtures/storage/v1-minimal.json").unwrap(),
+    )
+    .unwrap();
+    fixture["discord_channel_id"] = channel_id.into();
+    fixture["discord_activity_thread_id"] = thread_id.into();
+    std::fs::write(
+        harness
+            .storage_folder
+            .join(format!("char-{}.json", CHARACTER_ID)),
+        fixture.to_string(),
+    )
+    .unwrap();
+
+    mock.add_character(CHARACTER_ID, "Old Pilot");
+    mock.add_refresh_token(CHARACTER_ID, "refresh-v1-minimal");
+    mock.set_skills(CHARACTER_ID, skills(vec![skill(3300, 3, 45255)]));
+
+    harness.monitor.load_all_eve_characters().await;
+    assert!(harness.monitor.refresh_now(CHARACTER_ID).await);
+
+    let messages = recorder.messages(channel_id);
+    assert_eq!(messages.len(), 1);
+    assert_ne!(messages[0], "Old status");
+    assert_eq!(recorder.pins(channel_id), vec![message_id]);
+
+    let storage = harness.monitor.read_from_storage(CHARACTER_ID).unwrap();
+    assert_eq!(storage.discord_status_message_id, Some(message_id));
+}
+
+fn status_edits(recorder: &RecordingNotifier) -> usize {
+    recorder
+        .notifications()
+        .into_iter()
+        .filter(|n| {
+            matches!(
+                n,
+                eve_skills_discord::notifier::Notification::EditMessage { .. }
+            )
+        })
+        .count()
+}
+
+#[tokio::test]
+async fn test_status_not_edited_without_changes() {
+    let harness = Harness::start("status-current", StorageKind::Json).await;
+    let mock = &harness.mock;
+    let recorder = &harness.recorder;
+
+    mock.add_character(CHARACTER_ID, "Mock Pilot");
+    mock.set_skills(CHARACTER_ID, skills(vec![skill(3301, 2, 1415)]));
+    mock.set_skill_queue(CHARACTER_ID, json!([]));
+    harness.authenticate(CHARACTER_ID, "status-current").await;
+
+    assert!(harness.monitor.refresh_now(CHARACTER_ID).await);
+    let edits = status_edits(recorder);
+
+    /* ESI has nothing new; Discord isn't bothered either. */
+    assert!(harness.monitor.refresh_now(CHARACTER_ID).await);
+    assert_eq!(status_edits(recorder), edits);
+}
+
+#[tokio::test]
+async fn test_status_not_edited_while_progress_unchanged() {
+    let harness = Harness::start("status-progress", StorageKind::Json).await;
+    let mock = &harness.mock;
+    let recorder = &harness.recorder;
+
+    let now = chrono::Utc::now();
+    mock.add_character(CHARACTER_ID, "Mock Pilot");
+    mock.set_skills(CHARACTER_ID, skills(vec![skill(3301, 2, 1415)]));
+    mock.set_skill_queue(
+        CHARACTER_ID,
+        json!([queue_item(3301, 3, now, now + chrono::TimeDelta::hours(48))]),
+    );
+    harness.authenticate(CHARACTER_ID, "status-progress").await;
+
+    assert!(harness.monitor.refresh_now(CHARACTER_ID).await);
+    let edits = status_edits(recorder);
+
+    /* Still training, but the progress shown is the same as before. */
+    assert!(harness.monitor.refresh_now(CHARACTER_ID).await);
+    assert_eq!(status_edits(recorder), edits);
+}
+
+#[tokio::test]
+async fn test_uninstall_archives_channel() {
+    let harness = Harness::start("archive", StorageKind::Json).await;
+    let mock = &harness.mock;
+    let recorder = &harness.recorder;
+
+    mock.add_character(CHARACTER_ID, "Mock Pilot");
+    mock.set_name(3301, "Small Hybrid Turret");
+    mock.set_skills(CHARACTER_ID, skills(vec![skill(3301, 2, 1415)]));
+    mock.set_skill_queue(CHARACTER_ID, json!([]));
+    harness.authenticate(CHARACTER_ID, "archive").await;
+    assert!(harness.monitor.refresh_now(CHARACTER_ID).await);
+    let storage = harness.monitor.read_from_storage(CHARACTER_ID).unwrap();
+    let channel_id = storage.discord_channel_id;
+    let thread_id = storage.discord_activity_thread_id;
+
+    harness
+        .monitor
+        .uninstall(CHARACTER_ID, DISCORD_USER_ID, false, ChannelAction::Archive)
+        .await
+        .unwrap();
+    assert!(
+        harness
+            .monitor
+            .read_from_storage(CHARACTER_ID)
+            .unwrap()
+            .expired
+    );
+    assert!(!harness.monitor.has_eve_character(CHARACTER_ID).await);
+    assert!(recorder.notifications().contains(
+        &eve_skills_discord::notifier::Notification::ArchiveChannel {
+            channel_id,
+            thread_id
+        }
+    ));
+    assert!(recorder.send_message(channel_id, "Hello").await.is_err());
+
+    /* Monitoring again reuses the channel, which can be posted in again. */
+    let response = harness.authenticate(CHARACTER_ID, "archive-again").await;
+    assert_eq!(
+        response,
+        format!("Your character is now monitored in <#{}>", channel_id)
+    );
+    mock.set_skills(CHARACTER_ID, skills(vec![skill(3301, 3, 8000)]));
+    assert!(harness.monitor.refresh_now(CHARACTER_ID).await);
+    assert_eq!(
+        recorder.messages(thread_id).last().unwrap(),
+        "`Small Hybrid Turret III` has finished training.\n"
+    );
+}
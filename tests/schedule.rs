@@ -0,0 +1,94 @@
+use chrono::{DateTime, TimeDelta, Utc};
+use eve_skills_discord::esi::{EsiSkillQueue, EsiSkillQueueItem};
+use eve_skills_discord::monitor::next_update;
+
+fn queue(finish_dates: &[DateTime<Utc>]) -> EsiSkillQueue {
+    EsiSkillQueue(
+        finish_dates
+            .iter()
+            .enumerate()
+            .map(|(position, finish_date)| EsiSkillQueueItem {
+                finish_date: Some(*finish_date),
+                finished_level: 4,
+                level_end_sp: 135765,
+                level_start_sp: 45255,
+                queue_position: position as i32,
+                skill_id: 3300 + position as i32,
+                start_date: None,
+                training_start_sp: 45255,
+            })
+            .collect(),
+    )
+}
+
+/* next_update looks at the clock itself; bracket the call to check intervals relative to now. */
+fn assert_after(
+    skill_queue: &EsiSkillQueue,
+    expires: Option<DateTime<Utc>>,
+    queue_warnings: &[TimeDelta],
+    interval: TimeDelta,
+) {
+    let before = Utc::now();
+    let next = next_update(skill_queue, expires, queue_warnings);
+    let after = Utc::now();
+
+    assert!(before + interval <= next && next <= after + interval);
+}
+
+#[test]
+fn test_next_finish_plus_grace() {
+    let now = Utc::now();
+    let finish = now + TimeDelta::minutes(10);
+
+    /* Skills that already finished are ignored. */
+    let skill_queue = queue(&[now - TimeDelta::minutes(5), finish]);
+    assert_eq!(
+        next_update(&skill_queue, None, &[]),
+        finish + TimeDelta::seconds(60)
+    );
+}
+
+#[test]
+fn test_capped_while_training() {
+    let skill_queue = queue(&[Utc::now() + TimeDelta::hours(5)]);
+    assert_after(&skill_queue, None, &[], TimeDelta::minutes(30));
+}
+
+#[test]
+fn test_idle_interval() {
+    assert_after(&queue(&[]), None, &[], TimeDelta::hours(2));
+
+    let skill_queue = queue(&[Utc::now() - TimeDelta::minutes(5)]);
+    assert_after(&skill_queue, None, &[], TimeDelta::hours(2));
+}
+
+#[test]
+fn test_clamped_to_expires() {
+    let now = Utc::now();
+    let expires = now + TimeDelta::minutes(20);
+
+    let skill_queue = queue(&[now + TimeDelta::minutes(10)]);
+    assert_eq!(next_update(&skill_queue, Some(expires), &[]), expires);
+
+    /* An expiry in the past changes nothing. */
+    let finish = now + TimeDelta::minutes(10);
+    let skill_queue = queue(&[finish]);
+    assert_eq!(
+        next_update(&skill_queue, Some(now - TimeDelta::minutes(1)), &[]),
+        finish + TimeDelta::seconds(60)
+    );
+}
+
+#[test]
+fn test_pulled_in_for_queue_warning() {
+    let now = Utc::now();
+    let queue_end = now + TimeDelta::minutes(70);
+    let skill_queue = queue(&[queue_end]);
+
+    /* The 24h warning is already behind us; the 1h warning is due in 10 minutes. */
+    let queue_warnings = [TimeDelta::hours(24), TimeDelta::hours(1)];
+    assert_eq!(
+        next_update(&skill_queue, None, &queue_warnings),
+        queue_end - TimeDelta::hours(1)
+    );
+}
@@ -0,0 +1,142 @@
+use eve_skills_discord::esi::EsiSkill;
+use eve_skills_discord::monitor::{skills_diff, SkillChange};
+
+fn skill(skill_id: i32, level: i32, skillpoints: i32) -> EsiSkill {
+    EsiSkill {
+        skill_id,
+        skillpoints_in_skill: skillpoints,
+        trained_skill_level: level,
+        active_skill_level: level,
+    }
+}
+
+#[test]
+fn test_no_changes() {
+    let skills = vec![skill(3300, 3, 40000), skill(3301, 2, 1415)];
+    assert!(skills_diff(&skills, &skills).is_empty());
+}
+
+#[test]
+fn test_finished_and_injected() {
+    let old = vec![skill(3300, 3, 40000)];
+    let new = vec![
+        skill(3300, 4, 135765),
+        skill(3301, 0, 0),
+        skill(3302, 1, 250),
+    ];
+
+    assert_eq!(
+        skills_diff(&old, &new),
+        vec![
+            SkillChange::Finished {
+                skill_id: 3300,
+                level: 4
+            },
+            SkillChange::Injected { skill_id: 3301 },
+            SkillChange::Finished {
+                skill_id: 3302,
+                level: 1
+            },
+        ]
+    );
+}
+
+#[test]
+fn test_training_progress_is_no_change() {
+    let old = vec![skill(3300, 3, 40000)];
+    let new = vec![skill(3300, 3, 52000)];
+
+    assert!(skills_diff(&old, &new).is_empty());
+}
+
+#[test]
+fn test_extracted_without_level_loss() {
+    let old = vec![skill(3300, 4, 200000)];
+    let new = vec![skill(3300, 4, 150000)];
+
+    assert_eq!(
+        skills_diff(&old, &new),
+        vec![SkillChange::Extracted {
+            skill_id: 3300,
+            sp_removed: 50000
+        }]
+    );
+}
+
+#[test]
+fn test_level_lost() {
+    let old = vec![skill(3300, 5, 256000)];
+    let new = vec![skill(3300, 4, 45255)];
+
+    assert_eq!(
+        skills_diff(&old, &new),
+        vec![SkillChange::LevelLost {
+            skill_id: 3300,
+            old_level: 5,
+            new_level: 4,
+            sp_removed: 210745
+        }]
+    );
+    assert_eq!(
+        skills_diff(&old, &new)[0].describe("Gunnery"),
+        "`Gunnery` has dropped from level V to IV; 210,745 SP has been extracted.\n"
+    );
+}
+
+#[test]
+fn test_level_lost_to_untrained() {
+    let old = vec![skill(3300, 1, 250)];
+    let new = vec![skill(3300, 0, 100)];
+
+    let changes = skills_diff(&old, &new);
+    assert_eq!(
+        changes,
+        vec![SkillChange::LevelLost {
+            skill_id: 3300,
+            old_level: 1,
+            new_level: 0,
+            sp_removed: 150
+        }]
+    );
+    assert_eq!(
+        changes[0].describe("Gunnery"),
+        "`Gunnery` has dropped from level I to untrained; 150 SP has been extracted.\n"
+    );
+}
+
+#[test]
+fn test_removed() {
+    let old = vec![skill(3300, 3, 40000), skill(3301, 1, 250)];
+    let new = vec![skill(3300, 3, 40000)];
+
+    assert_eq!(
+        skills_diff(&old, &new),
+        vec![SkillChange::Removed {
+            skill_id: 3301,
+            sp_removed: 250
+        }]
+    );
+}
+
+#[test]
+fn test_extraction_is_not_reported_as_finished() {
+    /* Before, any level change was reported as "finished training". */
+    let old = vec![skill(3300, 2, 8000), skill(3301, 2, 1415)];
+    let new = vec![skill(3300, 1, 500), skill(3301, 3, 8000)];
+
+    assert_eq!(
+        skills_diff(&old, &new),
+        vec![
+            SkillChange::LevelLost {
+                skill_id: 3300,
+                old_level: 2,
+                new_level: 1,
+                sp_removed: 7500
+            },
+            SkillChange::Finished {
+                skill_id: 3301,
+                level: 3
+            },
+        ]
+    );
+}
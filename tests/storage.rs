@@ -0,0 +1,379 @@
+use eve_skills_discord::monitor::{
+    rotate_storage_key, JsonStorage, SqliteStorage, StorageBackend, StorageConfig, StorageKey,
+    StorageKind, StorageLock, StorageV2,
+};
+
+fn character(eve_character_id: u64, discord_character_id: u64, discord_guild_id: u64) -> StorageV2 {
+    StorageV2 {
+        refresh_token: format!("refresh-{}", eve_character_id),
+        expired: false,
+        eve_character_id,
+        eve_character_name: format!("Pilot {}", eve_character_id),
+        scopes: Vec::new(),
+        discord_character_id,
+        discord_guild_id,
+        discord_channel_id: 10,
+        discord_activity_thread_id: 11,
+        discord_status_message_id: Some(12),
+        discord_status_hash: None,
+        skills: Vec::new(),
+        skill_queue: Vec::new(),
+        total_sp: 0,
+        unallocated_sp: 0,
+        history: Default::default(),
+        queue_warnings: Vec::new(),
+    }
+}
+
+fn key() -> StorageKey {
+    StorageKey::parse(&StorageKey::generate()).unwrap()
+}
+
+fn storage_folder(name: &str) -> String {
+    let folder = std::env::temp_dir().join(format!(
+        "eve-skills-discord-{}-{}",
+        name,
+        std::process::id()
+    ));
+    let _ = std::fs::remove_dir_all(&folder);
+    std::fs::create_dir_all(&folder).unwrap();
+    folder.to_str().unwrap().to_string()
+}
+
+/* Temporary files left behind by writes. */
+fn temp_files(folder: &str) -> usize {
+    std::fs::read_dir(folder)
+        .unwrap()
+        .filter(|f| {
+            f.as_ref()
+                .unwrap()
+                .file_name()
+                .to_str()
+                .unwrap()
+                .ends_with(".tmp")
+        })
+        .count()
+}
+
+#[test]
+fn test_sqlite_imports_json_once() {
+    let folder = storage_folder("import");
+    let key = key();
+
+    let json = JsonStorage::new(&folder, key.clone());
+    json.write(&character(1, 100, 1000)).unwrap();
+    json.write(&character(2, 100, 2000)).unwrap();
+    json.write(&character(3, 200, 1000)).unwrap();
+    std::fs::write(format!("{}/char-4.json", folder), "not json").unwrap();
+    /* A character stored before tokens were encrypted. */
+    let v1 = std::fs::read_to_string("tests/fixtures/storage/v1-minimal.json").unwrap();
+    std::fs::write(format!("{}/char-90000001.json", folder), &v1).unwrap();
+
+    let sqlite = SqliteStorage::open(&folder, key.clone()).unwrap();
+    let mut ids = sqlite.list().unwrap();
+    ids.sort();
+    assert_eq!(ids, vec![1, 2, 3, 90000001]);
+    assert_eq!(sqlite.read(2).unwrap().refresh_token, "refresh-2");
+    assert_eq!(
+        sqlite.read(90000001).unwrap().refresh_token,
+        "refresh-v1-minimal"
+    );
+
+    /* The JSON files are left untouched, unreadable or not; only plain text tokens are encrypted. */
+    assert_eq!(
+        std::fs::read_to_string(format!("{}/char-4.json", folder)).unwrap(),
+        "not json"
+    );
+    let imported = std::fs::read_to_string(format!("{}/char-90000001.json", folder)).unwrap();
+    assert!(!imported.contains("refresh-v1-minimal"));
+    assert_eq!(
+        json.read(90000001).unwrap().refresh_token,
+        "refresh-v1-minimal"
+    );
+
+    let mut ids = sqlite.list_by_discord_user(100).unwrap();
+    ids.sort();
+    assert_eq!(ids, vec![1, 2]);
+    let mut ids = sqlite.list_by_discord_guild(1000).unwrap();
+    ids.sort();
+    assert_eq!(ids, vec![1, 3, 90000001]);
+
+    sqlite.delete(1).unwrap();
+    assert!(sqlite.read(1).is_err());
+    drop(sqlite);
+
+    /* Reopening doesn't import the JSON files again. */
+    let sqlite = SqliteStorage::open(&folder, key.clone()).unwrap();
+    let mut ids = sqlite.list().unwrap();
+    ids.sort();
+    assert_eq!(ids, vec![2, 3, 90000001]);
+}
+
+#[test]
+fn test_json_write_keeps_backup() {
+    let folder = storage_folder("backup");
+    let key = key();
+
+    let json = JsonStorage::new(&folder, key.clone());
+    let mut storage = character(1, 100, 1000);
+    json.write(&storage).unwrap();
+    storage.refresh_token = "refresh-new".to_string();
+    json.write(&storage).unwrap();
+
+    assert_eq!(json.read(1).unwrap().refresh_token, "refresh-new");
+    assert_eq!(temp_files(&folder), 0);
+
+    /* Restoring the backup gives the previous version. */
+    std::fs::copy(
+        format!("{}/char-1.json.bak", folder),
+        format!("{}/char-1.json", folder),
+    )
+    .unwrap();
+    assert_eq!(json.read(1).unwrap().refresh_token, "refresh-1");
+
+    json.delete(1).unwrap();
+    assert!(!std::path::Path::new(&format!("{}/char-1.json.bak", folder)).exists());
+}
+
+#[test]
+fn test_json_quarantines_corrupt_files() {
+    let folder = storage_folder("corrupt");
+    let key = key();
+
+    let json = JsonStorage::new(&folder, key.clone());
+    json.write(&character(1, 100, 1000)).unwrap();
+    std::fs::write(format!("{}/char-2.json", folder), "{\"version\":\"1\",").unwrap();
+
+    assert!(json.read(2).is_err());
+    assert!(std::path::Path::new(&format!("{}/char-2.json.corrupt", folder)).exists());
+    assert_eq!(json.list().unwrap(), vec![1]);
+}
+
+#[test]
+fn test_json_keeps_files_on_io_errors() {
+    let folder = storage_folder("io-error");
+    let json = JsonStorage::new(&folder, key());
+
+    /* Reading a directory fails, but the file itself is not at fault. */
+    std::fs::create_dir(format!("{}/char-3.json", folder)).unwrap();
+    assert!(json.read(3).is_err());
+    assert!(std::path::Path::new(&format!("{}/char-3.json", folder)).is_dir());
+    assert!(!std::path::Path::new(&format!("{}/char-3.json.corrupt", folder)).exists());
+}
+
+#[test]
+fn test_json_concurrent_writes() {
+    let folder = storage_folder("concurrent");
+    let json = std::sync::Arc::new(JsonStorage::new(&folder, key()));
+
+    let writers: Vec<_> = (0..8)
+        .map(|index| {
+            let json = json.clone();
+            std::thread::spawn(move || {
+                let mut storage = character(1, 100, 1000);
+                storage.total_sp = index;
+                for _ in 0..20 {
+                    json.write(&storage).unwrap();
+                }
+            })
+        })
+        .collect();
+    for writer in writers {
+        writer.join().unwrap();
+    }
+
+    assert!(json.read(1).is_ok());
+    assert_eq!(temp_files(&folder), 0);
+}
+
+#[test]
+fn test_refresh_tokens_are_encrypted() {
+    let folder = storage_folder("encrypted");
+    let key = key();
+
+    /* A character as written before tokens were encrypted. */
+    let path = format!("{}/char-1.json", folder);
+    let mut v1 = serde_json::to_value(character(1, 100, 1000)).unwrap();
+    v1["version"] = "1".into();
+    std::fs::write(&path, v1.to_string()).unwrap();
+
+    let json = JsonStorage::new(&folder, key.clone());
+    assert_eq!(json.read(1).unwrap().refresh_token, "refresh-1");
+
+    /* Upgraded on first read, without a plain text copy left behind. */
+    let stored = std::fs::read_to_string(&path).unwrap();
+    assert!(stored.contains("\"version\":\"2\""));
+    assert!(!stored.contains("refresh-1"));
+    assert!(!std::path::Path::new(&format!("{}.bak", path)).exists());
+
+    /* Without the right key, the token can't be read. */
+    assert!(JsonStorage::new(&folder, self::key()).read(1).is_err());
+    assert!(std::path::Path::new(&path).exists());
+}
+
+#[test]
+fn test_rotate_storage_key() {
+    for kind in [StorageKind::Json, StorageKind::Sqlite] {
+        let folder = storage_folder(&format!("rotate-{:?}", kind));
+        let config = StorageConfig {
+            folder: folder.clone(),
+            kind,
+            key: key(),
+        };
+        let new_key = key();
+
+        let json = JsonStorage::new(&folder, config.key.clone());
+        json.write(&character(1, 100, 1000)).unwrap();
+        json.write(&character(2, 100, 1000)).unwrap();
+        json.write(&character(2, 100, 1000)).unwrap();
+
+        assert_eq!(rotate_storage_key(&config, new_key.clone()).unwrap(), 2);
+        /* Running it again is harmless. */
+        assert_eq!(rotate_storage_key(&config, new_key.clone()).unwrap(), 0);
+
+        let rotated = StorageConfig {
+            key: new_key,
+            ..config.clone()
+        };
+        for eve_character_id in [1, 2] {
+            let storage = match kind {
+                StorageKind::Json => {
+                    JsonStorage::new(&rotated.folder, rotated.key.clone()).read(eve_character_id)
+                }
+                StorageKind::Sqlite => SqliteStorage::open(&rotated.folder, rotated.key.clone())
+                    .unwrap()
+                    .read(eve_character_id),
+            };
+            assert_eq!(
+                storage.unwrap().refresh_token,
+                format!("refresh-{}", eve_character_id)
+            );
+        }
+        if kind == StorageKind::Json {
+            assert!(!std::path::Path::new(&format!("{}/char-2.json.bak", folder)).exists());
+        }
+    }
+}
+
+/* Copy a fixture into a fresh storage folder, as the character with the given ID. */
+fn storage_with_fixture(name: &str, fixture: &str, eve_character_id: u64) -> String {
+    let folder = storage_folder(name);
+    std::fs::copy(
+        format!(
+            "{}/tests/fixtures/storage/{}",
+            env!("CARGO_MANIFEST_DIR"),
+            fixture
+        ),
+        format!("{}/char-{}.json", folder, eve_character_id),
+    )
+    .unwrap();
+    folder
+}
+
+#[test]
+fn test_migrate_minimal_v1_fixture() {
+    let folder = storage_with_fixture("fixture-minimal", "v1-minimal.json", 90000001);
+    let key = key();
+
+    let json = JsonStorage::new(&folder, key.clone());
+    let storage = json.read(90000001).unwrap();
+    assert_eq!(storage.refresh_token, "refresh-v1-minimal");
+    assert_eq!(storage.eve_character_name, "Old Pilot");
+    assert!(!storage.expired);
+    assert_eq!(storage.discord_channel_id, 10);
+    assert_eq!(storage.skills.len(), 1);
+    /* Fields that didn't exist yet get their defaults. */
+    assert!(storage.scopes.is_empty());
+    assert!(storage.skill_queue.is_empty());
+    assert_eq!(storage.total_sp, 0);
+    assert_eq!(storage.unallocated_sp, 0);
+
+    /* The upgraded version is written back, and reads the same. */
+    let stored: serde_json::Value = serde_json::from_str(
+        &std::fs::read_to_string(format!("{}/char-90000001.json", folder)).unwrap(),
+    )
+    .unwrap();
+    assert_eq!(stored["version"], "2");
+    assert_eq!(stored["total_sp"], 0);
+    assert_ne!(stored["refresh_token"], "refresh-v1-minimal");
+
+    let reread = json.read(90000001).unwrap();
+    assert_eq!(reread.refresh_token, "refresh-v1-minimal");
+    assert_eq!(reread.skills, storage.skills);
+}
+
+#[test]
+fn test_migrate_full_v1_fixture() {
+    for kind in [StorageKind::Json, StorageKind::Sqlite] {
+        let folder = storage_with_fixture(
+            &format!("fixture-full-{:?}", kind),
+            "v1-full.json",
+            90000002,
+        );
+        let key = key();
+
+        let storage: Box<dyn StorageBackend> = match kind {
+            StorageKind::Json => Box::new(JsonStorage::new(&folder, key.clone())),
+            StorageKind::Sqlite => Box::new(SqliteStorage::open(&folder, key.clone()).unwrap()),
+        };
+
+        let character = storage.read(90000002).unwrap();
+        assert_eq!(character.refresh_token, "refresh-v1-full");
+        assert!(character.expired);
+        assert_eq!(
+            character.scopes,
+            vec!["esi-skills.read_skills.v1", "esi-skills.read_skillqueue.v1"]
+        );
+        assert_eq!(character.skills.len(), 2);
+        assert_eq!(character.skill_queue.len(), 1);
+        assert_eq!(character.skill_queue[0].skill_id, 3301);
+        assert_eq!(character.total_sp, 53255);
+        assert_eq!(character.unallocated_sp, 1000);
+        assert_eq!(storage.list_by_discord_user(2000).unwrap(), vec![90000002]);
+    }
+}
+
+#[test]
+fn test_rotate_storage_key_refuses_while_in_use() {
+    let folder = storage_folder("rotate-in-use");
+    let config = StorageConfig {
+        folder: folder.clone(),
+        kind: StorageKind::Json,
+        key: key(),
+    };
+    JsonStorage::new(&folder, config.key.clone())
+        .write(&character(1, 100, 1000))
+        .unwrap();
+
+    /* As held by a running bot. */
+    let lock = StorageLock::acquire(&folder).unwrap();
+    assert!(rotate_storage_key(&config, key()).is_err());
+
+    drop(lock);
+    assert_eq!(rotate_storage_key(&config, key()).unwrap(), 1);
+}
+
+#[test]
+fn test_rotate_storage_key_refuses_plain_text_files() {
+    let folder = storage_folder("rotate-plain-text");
+    let config = StorageConfig {
+        folder: folder.clone(),
+        kind: StorageKind::Sqlite,
+        key: key(),
+    };
+    SqliteStorage::open(&folder, config.key.clone())
+        .unwrap()
+        .write(&character(1, 100, 1000))
+        .unwrap();
+
+    /* Left behind from before tokens were encrypted; the database doesn't read it any more. */
+    let path = format!("{}/char-90000001.json.bak", folder);
+    std::fs::copy("tests/fixtures/storage/v1-minimal.json", &path).unwrap();
+    let new_key = key();
+    let error = rotate_storage_key(&config, new_key.clone()).unwrap_err();
+    assert!(error.contains(&path));
+
+    /* The characters themselves were rotated already. */
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!(rotate_storage_key(&config, new_key).unwrap(), 0);
+}